@@ -0,0 +1,284 @@
+use crate::{arrangement_table, ArrangementCount};
+
+/// For each position in a sorted adapter chain, whether there's any way to get from
+/// there to the device at the end.
+fn can_finish(chain: &[i64], max_step: i64) -> Vec<bool> {
+    let n = chain.len();
+    let mut finishes = vec![false; n];
+    finishes[n - 1] = true;
+    for current in (0..(n - 1)).rev() {
+        finishes[current] = ((current + 1)..n)
+            .take_while(|&next| chain[next] - chain[current] <= max_step)
+            .any(|next| finishes[next]);
+    }
+    finishes
+}
+
+/// An iterator over every valid arrangement of adapters, from the outlet to the device,
+/// in lexicographic order of joltages. Each chain is only built when it's asked for, so
+/// this can be stopped early even when there are trillions of arrangements.
+pub struct AdapterChains<'a> {
+    chain: &'a [i64],
+    max_step: i64,
+    finishes: Vec<bool>,
+    path: Vec<usize>,
+    started: bool,
+}
+
+impl<'a> AdapterChains<'a> {
+    /// Iterate over arrangements of a sorted adapter chain, as returned by
+    /// `adapter_chain`.
+    pub fn new(chain: &'a [i64], max_step: i64) -> Self {
+        AdapterChains {
+            chain,
+            max_step,
+            finishes: can_finish(chain, max_step),
+            path: Vec::new(),
+            started: false,
+        }
+    }
+
+    /// The first position after `after` that can follow `from` and still reach the end.
+    fn next_step(&self, from: usize, after: usize) -> Option<usize> {
+        ((after + 1)..self.chain.len())
+            .take_while(|&next| self.chain[next] - self.chain[from] <= self.max_step)
+            .find(|&next| self.finishes[next])
+    }
+
+    /// Extend the current path to the end, always taking the nearest next adapter.
+    fn extend_path(&mut self) {
+        let end = self.chain.len() - 1;
+        while let Some(&last) = self.path.last() {
+            if last == end {
+                break;
+            }
+            let next = self.next_step(last, last).unwrap();
+            self.path.push(next);
+        }
+    }
+
+    fn joltages(&self) -> Vec<i64> {
+        self.path.iter().map(|&pos| self.chain[pos]).collect()
+    }
+}
+
+impl<'a> Iterator for AdapterChains<'a> {
+    type Item = Vec<i64>;
+
+    fn next(&mut self) -> Option<Vec<i64>> {
+        if !self.started {
+            self.started = true;
+            if !self.finishes[0] {
+                return None;
+            }
+            self.path.push(0);
+            self.extend_path();
+            return Some(self.joltages());
+        }
+        while let Some(last) = self.path.pop() {
+            if let Some(&prev) = self.path.last() {
+                if let Some(next) = self.next_step(prev, last) {
+                    self.path.push(next);
+                    self.extend_path();
+                    return Some(self.joltages());
+                }
+            }
+        }
+        None
+    }
+}
+
+/// A small, fast pseudo-random number generator (SplitMix64), so that sampling chains
+/// can be repeated from a seed.
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        SplitMix64 { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// A uniformly random number in 0..bound, using rejection sampling to avoid bias.
+    pub fn below(&mut self, bound: u128) -> u128 {
+        assert!(bound > 0);
+        let zone = u128::MAX - (u128::MAX % bound);
+        loop {
+            let value = ((self.next_u64() as u128) << 64) | self.next_u64() as u128;
+            if value < zone {
+                return value % bound;
+            }
+        }
+    }
+}
+
+/// An arrangement count that can also be used to sample arrangements, which needs
+/// comparisons, subtraction, and a way to draw a random count.
+pub trait SampleCount: ArrangementCount + Ord {
+    fn minus(&self, other: &Self) -> Self;
+
+    /// A uniformly random count in 0..bound.
+    fn random_below(rng: &mut SplitMix64, bound: &Self) -> Self;
+}
+
+macro_rules! impl_sample_count {
+    ($($t:ty),*) => {
+        $(
+            impl SampleCount for $t {
+                fn minus(&self, other: &Self) -> Self {
+                    self - other
+                }
+
+                fn random_below(rng: &mut SplitMix64, bound: &Self) -> Self {
+                    rng.below(*bound as u128) as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_sample_count!(u32, u64, u128, usize);
+
+/// Pick one of the valid arrangements of a sorted adapter chain, uniformly at random.
+/// Returns None if there are no valid arrangements.
+///
+/// This walks backward from the device, choosing each previous adapter with probability
+/// proportional to the number of arrangements that reach it, using the table from
+/// `arrangement_table`. The counts are kept in `T`, which has to be wide enough for the
+/// number of arrangements, just like in `joltage_arrangements_as`.
+pub fn sample_chain<T: SampleCount>(
+    chain: &[i64],
+    max_step: i64,
+    rng: &mut SplitMix64,
+) -> Option<Vec<i64>> {
+    let table: Vec<T> = arrangement_table(chain, max_step);
+    let mut current = chain.len() - 1;
+    if table[current] == T::zero() {
+        return None;
+    }
+    let mut reversed: Vec<i64> = vec![chain[current]];
+    while current > 0 {
+        let mut choice = T::random_below(rng, &table[current]);
+        let mut prev = current - 1;
+        while choice >= table[prev] {
+            choice = choice.minus(&table[prev]);
+            prev -= 1;
+        }
+        reversed.push(chain[prev]);
+        current = prev;
+    }
+    reversed.reverse();
+    Some(reversed)
+}
+
+/// Find the valid arrangement with the fewest or most adapters, by dynamic programming
+/// over the number of steps needed to reach each position.
+fn extreme_chain(chain: &[i64], max_step: i64, longest: bool) -> Option<Vec<i64>> {
+    let n = chain.len();
+    let mut steps_to: Vec<Option<usize>> = vec![None; n];
+    let mut came_from: Vec<usize> = vec![0; n];
+    steps_to[0] = Some(0);
+    for current in 1..n {
+        let mut prev = current;
+        while prev > 0 && chain[prev - 1] >= chain[current] - max_step {
+            prev -= 1;
+            if let Some(steps) = steps_to[prev] {
+                let better = match steps_to[current] {
+                    None => true,
+                    Some(best) if longest => steps + 1 > best,
+                    Some(best) => steps + 1 < best,
+                };
+                if better {
+                    steps_to[current] = Some(steps + 1);
+                    came_from[current] = prev;
+                }
+            }
+        }
+    }
+    steps_to[n - 1]?;
+
+    let mut current = n - 1;
+    let mut reversed: Vec<i64> = vec![chain[current]];
+    while current > 0 {
+        current = came_from[current];
+        reversed.push(chain[current]);
+    }
+    reversed.reverse();
+    Some(reversed)
+}
+
+/// The valid arrangement that uses the fewest adapters.
+pub fn shortest_chain(chain: &[i64], max_step: i64) -> Option<Vec<i64>> {
+    extreme_chain(chain, max_step, false)
+}
+
+/// The valid arrangement that uses the most adapters.
+pub fn longest_chain(chain: &[i64], max_step: i64) -> Option<Vec<i64>> {
+    extreme_chain(chain, max_step, true)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{adapter_chain, DEFAULT_MAX_STEP};
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_enumerate_chains() {
+        let example: Vec<i64> = vec![16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4];
+        let chain = adapter_chain(&example, DEFAULT_MAX_STEP);
+        let all: Vec<Vec<i64>> = AdapterChains::new(&chain, DEFAULT_MAX_STEP).collect();
+        assert_eq!(all.len(), 8);
+        assert_eq!(all[0], vec![0, 1, 4, 5, 6, 7, 10, 11, 12, 15, 16, 19, 22]);
+        assert_eq!(all[7], vec![0, 1, 4, 7, 10, 12, 15, 16, 19, 22]);
+        let mut sorted = all.clone();
+        sorted.sort();
+        assert_eq!(all, sorted);
+
+        let long_run: Vec<i64> = (1..=100).collect();
+        let chain = adapter_chain(&long_run, DEFAULT_MAX_STEP);
+        assert_eq!(
+            AdapterChains::new(&chain, DEFAULT_MAX_STEP).take(5).count(),
+            5
+        );
+
+        let chain = adapter_chain(&example, 1);
+        assert_eq!(AdapterChains::new(&chain, 1).next(), None);
+    }
+
+    #[test]
+    fn test_sample_chains() {
+        let example: Vec<i64> = vec![16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4];
+        let chain = adapter_chain(&example, DEFAULT_MAX_STEP);
+        let all: HashSet<Vec<i64>> = AdapterChains::new(&chain, DEFAULT_MAX_STEP).collect();
+        let mut rng = SplitMix64::new(2020);
+        let sampled: HashSet<Vec<i64>> = (0..200)
+            .map(|_| sample_chain::<u128>(&chain, DEFAULT_MAX_STEP, &mut rng).unwrap())
+            .collect();
+        assert_eq!(sampled, all);
+
+        // Narrower count types sample the same way
+        let sampled: HashSet<Vec<i64>> = (0..200)
+            .map(|_| sample_chain::<u32>(&chain, DEFAULT_MAX_STEP, &mut rng).unwrap())
+            .collect();
+        assert_eq!(sampled, all);
+        assert_eq!(sample_chain::<u64>(&chain, 1, &mut rng), None);
+    }
+
+    #[test]
+    fn test_extreme_chains() {
+        let example: Vec<i64> = vec![16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4];
+        let chain = adapter_chain(&example, DEFAULT_MAX_STEP);
+        assert_eq!(shortest_chain(&chain, DEFAULT_MAX_STEP).unwrap().len(), 10);
+        assert_eq!(longest_chain(&chain, DEFAULT_MAX_STEP).unwrap(), chain);
+        assert_eq!(shortest_chain(&chain, 1), None);
+    }
+}
@@ -1,15 +1,17 @@
+mod chains;
 mod helpers;
+use chains::{longest_chain, sample_chain, shortest_chain, AdapterChains, SplitMix64};
 use helpers::get_lines;
 
-pub fn joltage_rating(jolts: &Vec<i64>) -> i64 {
-    let mut sorted_jolts = jolts.clone();
+pub fn joltage_rating(jolts: &[i64]) -> i64 {
+    let mut sorted_jolts = jolts.to_vec();
     sorted_jolts.push(0);
     sorted_jolts.sort();
     let mut diffs1: i64 = 0;
     let mut diffs3: i64 = 0;
     for i in 0..(sorted_jolts.len() - 1) {
         let diff = sorted_jolts[i + 1] - sorted_jolts[i];
-        assert!(1 <= diff && diff <= 3);
+        assert!((1..=3).contains(&diff));
         if diff == 1 {
             diffs1 += 1
         } else if diff == 3 {
@@ -20,26 +22,105 @@ pub fn joltage_rating(jolts: &Vec<i64>) -> i64 {
     diffs1 * (diffs3 + 1)
 }
 
-pub fn joltage_arrangements(jolts: &Vec<i64>) -> i64 {
-    let mut sorted_jolts = jolts.clone();
+/// The largest difference in joltage that an adapter can take in, in the puzzle's
+/// family of adapters.
+pub const DEFAULT_MAX_STEP: i64 = 3;
+
+/// A number type that arrangements can be counted in. Implementations should panic
+/// on overflow instead of wrapping, so that a count is never silently wrong.
+///
+/// This is implemented for the built-in unsigned integers; a big-integer type can be
+/// used by implementing it too.
+pub trait ArrangementCount: Clone {
+    fn zero() -> Self;
+    fn one() -> Self;
+    fn plus(&self, other: &Self) -> Self;
+}
+
+macro_rules! impl_arrangement_count {
+    ($($t:ty),*) => {
+        $(
+            impl ArrangementCount for $t {
+                fn zero() -> Self {
+                    0
+                }
+
+                fn one() -> Self {
+                    1
+                }
+
+                fn plus(&self, other: &Self) -> Self {
+                    self.checked_add(*other)
+                        .expect("arrangement count overflowed; use a wider type")
+                }
+            }
+        )*
+    };
+}
+
+impl_arrangement_count!(u32, u64, u128, usize);
+
+/// Sort the adapters into the order they'd be chained in, including the outlet at 0
+/// jolts and the device, which is `max_step` higher than the highest adapter.
+pub fn adapter_chain(jolts: &[i64], max_step: i64) -> Vec<i64> {
+    let mut sorted_jolts = jolts.to_vec();
     sorted_jolts.push(0);
     sorted_jolts.sort();
     let highest_jolts = sorted_jolts[sorted_jolts.len() - 1];
-    sorted_jolts.push(highest_jolts + 3);
+    sorted_jolts.push(highest_jolts + max_step);
+    sorted_jolts
+}
 
-    let n = sorted_jolts.len();
-    let mut arrangements_up_to: Vec<i64> = vec![0; n];
-    arrangements_up_to[0] = 1;
+/// Given a sorted adapter chain, count the ways to get from the outlet to each position
+/// in the chain, where each step increases by at most `max_step` jolts. The `zero`,
+/// `one` and `plus` arguments say how to do arithmetic on the counts.
+pub fn arrangement_table_with<T: Clone>(
+    chain: &[i64],
+    max_step: i64,
+    zero: T,
+    one: T,
+    plus: impl Fn(&T, &T) -> T,
+) -> Vec<T> {
+    let n = chain.len();
+    let mut arrangements_up_to: Vec<T> = vec![zero; n];
+    arrangements_up_to[0] = one;
 
     for current in 1..n {
-        let lower_bound: usize = if current >= 3 { current - 3 } else { 0 };
-        for prev in lower_bound..current {
-            if sorted_jolts[prev] >= sorted_jolts[current] - 3 {
-                arrangements_up_to[current] += arrangements_up_to[prev];
-            }
+        let mut prev = current;
+        while prev > 0 && chain[prev - 1] >= chain[current] - max_step {
+            prev -= 1;
+            arrangements_up_to[current] =
+                plus(&arrangements_up_to[current], &arrangements_up_to[prev]);
         }
     }
-    arrangements_up_to[n - 1]
+    arrangements_up_to
+}
+
+/// Count the ways to get from the outlet to each position in a sorted adapter chain.
+pub fn arrangement_table<T: ArrangementCount>(chain: &[i64], max_step: i64) -> Vec<T> {
+    arrangement_table_with(chain, max_step, T::zero(), T::one(), T::plus)
+}
+
+/// Count the arrangements of adapters that connect the outlet to the device, using
+/// any number type that implements `ArrangementCount`.
+pub fn joltage_arrangements_as<T: ArrangementCount>(jolts: &[i64], max_step: i64) -> T {
+    let chain = adapter_chain(jolts, max_step);
+    arrangement_table(&chain, max_step).pop().unwrap()
+}
+
+/// Count the arrangements of adapters, modulo `modulus` (which would usually be a
+/// large prime), for when the exact count is too large to be worth keeping.
+pub fn joltage_arrangements_mod(jolts: &[i64], max_step: i64, modulus: u64) -> u64 {
+    assert!(modulus > 0, "modulus must be positive");
+    let chain = adapter_chain(jolts, max_step);
+    let plus = |a: &u64, b: &u64| ((*a as u128 + *b as u128) % modulus as u128) as u64;
+    arrangement_table_with(&chain, max_step, 0, 1 % modulus, plus)
+        .pop()
+        .unwrap()
+}
+
+pub fn joltage_arrangements(jolts: &[i64]) -> u128 {
+    joltage_arrangements_as(jolts, DEFAULT_MAX_STEP)
 }
 
 fn main() {
@@ -52,6 +133,22 @@ fn main() {
     let arrangements = joltage_arrangements(&joltage);
     println!("Joltage rating of using all adapters: {}", rating);
     println!("Number of arrangements: {}", arrangements);
+
+    let chain = adapter_chain(&joltage, DEFAULT_MAX_STEP);
+    if let Some(shortest) = shortest_chain(&chain, DEFAULT_MAX_STEP) {
+        println!("Shortest chain uses {} adapters", shortest.len() - 2);
+    }
+    if let Some(longest) = longest_chain(&chain, DEFAULT_MAX_STEP) {
+        println!("Longest chain uses {} adapters", longest.len() - 2);
+    }
+    println!("Some example chains:");
+    for example in AdapterChains::new(&chain, DEFAULT_MAX_STEP).take(3) {
+        println!("{:?}", example);
+    }
+    let mut rng = SplitMix64::new(2020);
+    if let Some(sample) = sample_chain::<u128>(&chain, DEFAULT_MAX_STEP, &mut rng) {
+        println!("A random chain:\n{:?}", sample);
+    }
 }
 
 #[cfg(test)]
@@ -69,4 +166,22 @@ mod test {
         ];
         assert_eq!(joltage_arrangements(&example), 19208);
     }
+
+    #[test]
+    fn test_arrangement_types() {
+        let example: Vec<i64> = vec![16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4];
+        assert_eq!(joltage_arrangements_as::<u32>(&example, 3), 8);
+        assert_eq!(joltage_arrangements_mod(&example, 3, 5), 3);
+        assert_eq!(joltage_arrangements_as::<u64>(&example, 1), 0);
+        assert!(joltage_arrangements_as::<u64>(&example, 6) > 8);
+
+        // A long run of consecutive adapters has a tribonacci number of arrangements,
+        // which is much too large for an i64
+        let long_run: Vec<i64> = (1..=120).collect();
+        let exact: u128 = joltage_arrangements(&long_run);
+        assert_eq!(
+            joltage_arrangements_mod(&long_run, 3, 1_000_000_007) as u128,
+            exact % 1_000_000_007
+        );
+    }
 }
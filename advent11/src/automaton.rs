@@ -0,0 +1,218 @@
+use crate::Seat;
+use ndarray::Array2;
+
+/// A way of deciding which seats a person pays attention to when deciding whether to
+/// sit down or get up.
+///
+/// The neighbors of a position can depend on the layout of the floor, but never on
+/// which seats are occupied.
+pub trait Neighborhood {
+    fn neighbors(&self, grid: &Array2<Seat>, pos: (usize, usize)) -> Vec<(usize, usize)>;
+}
+
+/// Step from `pos` by the given offset, returning None if that leaves the grid.
+fn offset(
+    grid: &Array2<Seat>,
+    pos: (usize, usize),
+    d_row: isize,
+    d_col: isize,
+) -> Option<(usize, usize)> {
+    let (n_rows, n_cols) = grid.dim();
+    let row = pos.0 as isize + d_row;
+    let col = pos.1 as isize + d_col;
+    if row < 0 || row >= n_rows as isize || col < 0 || col >= n_cols as isize {
+        None
+    } else {
+        Some((row as usize, col as usize))
+    }
+}
+
+const DIRECTIONS: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+const KNIGHT_MOVES: [(isize, isize); 8] = [
+    (-2, -1),
+    (-2, 1),
+    (-1, -2),
+    (-1, 2),
+    (1, -2),
+    (1, 2),
+    (2, -1),
+    (2, 1),
+];
+
+/// The 8 positions immediately around a seat.
+pub struct Adjacent;
+
+impl Neighborhood for Adjacent {
+    fn neighbors(&self, grid: &Array2<Seat>, pos: (usize, usize)) -> Vec<(usize, usize)> {
+        DIRECTIONS
+            .iter()
+            .filter_map(|&(d_row, d_col)| offset(grid, pos, d_row, d_col))
+            .collect()
+    }
+}
+
+/// The first seat that can be seen in each of the 8 directions, looking past floor.
+pub struct LineOfSight;
+
+impl Neighborhood for LineOfSight {
+    fn neighbors(&self, grid: &Array2<Seat>, pos: (usize, usize)) -> Vec<(usize, usize)> {
+        let mut visible = Vec::new();
+        for &(d_row, d_col) in DIRECTIONS.iter() {
+            let mut current = pos;
+            while let Some(next) = offset(grid, current, d_row, d_col) {
+                if grid[next] != Seat::Floor {
+                    visible.push(next);
+                    break;
+                }
+                current = next;
+            }
+        }
+        visible
+    }
+}
+
+/// The 8 positions a chess knight could move to.
+pub struct KnightMoves;
+
+impl Neighborhood for KnightMoves {
+    fn neighbors(&self, grid: &Array2<Seat>, pos: (usize, usize)) -> Vec<(usize, usize)> {
+        KNIGHT_MOVES
+            .iter()
+            .filter_map(|&(d_row, d_col)| offset(grid, pos, d_row, d_col))
+            .collect()
+    }
+}
+
+/// Every position within a given number of steps in any direction, including
+/// diagonally, so `Radius(1)` is the same as `Adjacent`.
+pub struct Radius(pub usize);
+
+impl Neighborhood for Radius {
+    fn neighbors(&self, grid: &Array2<Seat>, pos: (usize, usize)) -> Vec<(usize, usize)> {
+        let r = self.0 as isize;
+        let mut nearby = Vec::new();
+        for d_row in -r..=r {
+            for d_col in -r..=r {
+                if d_row != 0 || d_col != 0 {
+                    if let Some(next) = offset(grid, pos, d_row, d_col) {
+                        nearby.push(next);
+                    }
+                }
+            }
+        }
+        nearby
+    }
+}
+
+/// The rules for how seats fill up and empty out. An empty seat becomes full when
+/// at most `max_to_sit` of its neighbors are full, and a full seat becomes empty
+/// when at least `min_to_leave` of its neighbors are full.
+pub struct SeatRules<N: Neighborhood> {
+    pub neighborhood: N,
+    pub max_to_sit: usize,
+    pub min_to_leave: usize,
+}
+
+/// The rules from part 1 of the puzzle.
+pub const ADJACENCY_RULES: SeatRules<Adjacent> = SeatRules {
+    neighborhood: Adjacent,
+    max_to_sit: 0,
+    min_to_leave: 4,
+};
+
+/// The rules from part 2 of the puzzle.
+pub const VISIBILITY_RULES: SeatRules<LineOfSight> = SeatRules {
+    neighborhood: LineOfSight,
+    max_to_sit: 0,
+    min_to_leave: 5,
+};
+
+/// Part 1's rules, but with people paying attention to the seats a knight's move away.
+pub const KNIGHT_RULES: SeatRules<KnightMoves> = SeatRules {
+    neighborhood: KnightMoves,
+    max_to_sit: 0,
+    min_to_leave: 4,
+};
+
+/// People pay attention to all 24 seats within two steps, and leave when half of them
+/// are full.
+pub const RADIUS_RULES: SeatRules<Radius> = SeatRules {
+    neighborhood: Radius(2),
+    max_to_sit: 0,
+    min_to_leave: 12,
+};
+
+impl<N: Neighborhood> SeatRules<N> {
+    /// Work out what a seat will become, given how many of its neighbors are full.
+    pub fn next_seat(&self, seat: Seat, num_full: usize) -> Seat {
+        match seat {
+            Seat::Empty if num_full <= self.max_to_sit => Seat::Full,
+            Seat::Full if num_full >= self.min_to_leave => Seat::Empty,
+            _ => seat,
+        }
+    }
+
+    /// Run one generation of the seating rules over the whole grid. `Simulation` is
+    /// faster, but this is the simplest statement of the rules to test it against.
+    #[allow(dead_code)]
+    pub fn step(&self, grid: &Array2<Seat>) -> Array2<Seat> {
+        let mut newgrid = grid.clone();
+        for (pos, &seat) in grid.indexed_iter() {
+            if seat != Seat::Floor {
+                let num_full = self
+                    .neighborhood
+                    .neighbors(grid, pos)
+                    .iter()
+                    .filter(|&&neighbor| grid[neighbor] == Seat::Full)
+                    .count();
+                newgrid[pos] = self.next_seat(seat, num_full);
+            }
+        }
+        newgrid
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lines_to_grid;
+
+    #[test]
+    fn test_neighborhoods() {
+        let lines: Vec<String> = [
+            ".......#.",
+            "...#.....",
+            ".#.......",
+            ".........",
+            "..#L....#",
+            "....#....",
+            ".........",
+            "#........",
+            "...#.....",
+        ]
+        .iter()
+        .map(|&line| line.replace('#', "L"))
+        .collect();
+        let grid = lines_to_grid(&lines);
+        // The empty seat in the middle of this example (with padding) is at (5, 4)
+        assert_eq!(LineOfSight.neighbors(&grid, (5, 4)).len(), 8);
+        assert_eq!(Adjacent.neighbors(&grid, (5, 4)).len(), 8);
+        assert_eq!(Adjacent.neighbors(&grid, (0, 0)).len(), 3);
+        assert_eq!(KnightMoves.neighbors(&grid, (0, 0)).len(), 2);
+        assert_eq!(Radius(2).neighbors(&grid, (5, 4)).len(), 24);
+        assert_eq!(
+            Radius(1).neighbors(&grid, (5, 4)),
+            Adjacent.neighbors(&grid, (5, 4))
+        );
+    }
+}
@@ -1,21 +1,26 @@
+mod automaton;
 mod helpers;
-use core::default::Default;
+mod render;
+mod simulation;
+use automaton::{
+    Neighborhood, SeatRules, ADJACENCY_RULES, KNIGHT_RULES, RADIUS_RULES, VISIBILITY_RULES,
+};
 use helpers::get_lines;
 use ndarray::Array2;
+use render::{GifRecorder, Glyphs, TerminalAnimation};
+use simulation::{run_until_stable, Outcome, Simulation};
+use std::env;
+use std::io;
+use std::time::Duration;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum Seat {
+    #[default]
     Floor,
     Empty,
     Full,
 }
 
-impl Default for Seat {
-    fn default() -> Self {
-        Seat::Floor
-    }
-}
-
 /// Read lines of the problem's file format into a 2d array of Seats.
 fn lines_to_grid(lines: &[String]) -> Array2<Seat> {
     let n_rows = lines.len();
@@ -35,135 +40,140 @@ fn lines_to_grid(lines: &[String]) -> Array2<Seat> {
     grid
 }
 
-fn update_grid(grid: &Array2<Seat>) -> Array2<Seat> {
-    let mut newgrid = grid.clone();
-    let (n_rows, n_cols) = grid.dim();
-
-    // Iterate over upper-left corners of windows we'll look at
-    // (do this instead of iterating over centers so that we don't have to
-    // subtract usizes)
-    for row in 0..(n_rows - 2) {
-        for col in 0..(n_cols - 2) {
-            let center_seat = grid[(row + 1, col + 1)];
-            if center_seat != Seat::Floor {
-                // count neighbors of (row + 1, col + 1)
-                let mut num_neighbors: u32 = 0;
-                for d_row in 0..=2 {
-                    for d_col in 0..=2 {
-                        if d_row != 1 || d_col != 1 {
-                            if grid[(row + d_row, col + d_col)] == Seat::Full {
-                                num_neighbors += 1;
-                            }
-                        }
-                    }
-                }
-
-                // make changes according to the rules
-                if center_seat == Seat::Full && num_neighbors >= 4 {
-                    newgrid[(row + 1, col + 1)] = Seat::Empty;
-                } else if center_seat == Seat::Empty && num_neighbors == 0 {
-                    newgrid[(row + 1, col + 1)] = Seat::Full;
-                }
-            }
-        }
-    }
+fn count_grid(grid: &Array2<Seat>) -> usize {
+    grid.iter().filter(|&&seat| seat == Seat::Full).count()
+}
 
-    newgrid
+/// Which rules to run and how to watch them, chosen on the command line.
+#[derive(Default)]
+struct Options {
+    /// Run only the rules with this name, instead of both parts of the puzzle.
+    rules: Option<String>,
+    /// Animate in the terminal, with this delay between frames.
+    animate: Option<Duration>,
+    /// Write an animated GIF with this filename prefix.
+    gif_prefix: Option<String>,
 }
 
-fn update_by_visibility(grid: &Array2<Seat>) -> Array2<Seat> {
-    let mut newgrid = grid.clone();
-    let (n_rows, n_cols) = grid.dim();
-
-    // Iterate over upper-left corners of windows we'll look at
-    // (do this instead of iterating over centers so that we don't have to
-    // subtract usizes)
-    for row in 1..n_rows {
-        for col in 1..n_cols {
-            let center_seat = grid[(row, col)];
-            if center_seat != Seat::Floor {
-                // count visible full seats from (row + 1, col + 1)
-                let mut num_visible: u32 = 0;
-                for d_row in -1..=1 {
-                    for d_col in -1..=1 {
-                        if d_row != 0 || d_col != 0 {
-                            let mut c_row = row as i32;
-                            let mut c_col = col as i32;
-                            loop {
-                                c_row += d_row;
-                                c_col += d_col;
-                                if c_row < 0
-                                    || c_row >= n_rows as i32
-                                    || c_col < 0
-                                    || c_col >= n_cols as i32
-                                {
-                                    break;
-                                }
-                                if grid[(c_row as usize, c_col as usize)] == Seat::Full {
-                                    num_visible += 1;
-                                    break;
-                                } else if grid[(c_row as usize, c_col as usize)] == Seat::Empty {
-                                    break;
-                                }
-                            }
-                        }
-                    }
+impl Options {
+    /// Parse `--rules NAME`, `--animate MILLISECONDS` and `--gif PREFIX` options.
+    fn from_args(args: &[String]) -> io::Result<Self> {
+        let mut options = Options::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--rules" => {
+                    let name = args
+                        .next()
+                        .ok_or_else(|| usage_error("--rules needs the name of some rules"))?;
+                    options.rules = Some(name.clone());
                 }
-
-                // make changes according to the rules
-                if center_seat == Seat::Full && num_visible >= 5 {
-                    newgrid[(row, col)] = Seat::Empty;
-                } else if center_seat == Seat::Empty && num_visible == 0 {
-                    newgrid[(row, col)] = Seat::Full;
+                "--animate" => {
+                    let millis: u64 = args
+                        .next()
+                        .and_then(|ms| ms.parse().ok())
+                        .ok_or_else(|| usage_error("--animate needs a delay in milliseconds"))?;
+                    options.animate = Some(Duration::from_millis(millis));
+                }
+                "--gif" => {
+                    let prefix = args
+                        .next()
+                        .ok_or_else(|| usage_error("--gif needs a filename prefix"))?;
+                    options.gif_prefix = Some(prefix.clone());
                 }
-                // let seat_char: char = match newgrid[(row, col)] {
-                //     Seat::Full => '#',
-                //     Seat::Empty => 'L',
-                //     Seat::Floor => '.',
-                // };
-                // print!("{}", seat_char);
+                other => return Err(usage_error(&format!("unknown option: {}", other))),
             }
         }
-        // println!("");
+        Ok(options)
     }
-
-    newgrid
 }
 
-fn count_grid(grid: &Array2<Seat>) -> usize {
-    grid.iter().filter(|&&seat| seat == Seat::Full).count()
+fn usage_error(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
 }
 
-/// set visibility=true to update by visibility,
-fn iterate_until_fixed_point(grid: &Array2<Seat>, visibility: bool) -> Array2<Seat> {
-    let mut grid = grid.clone();
-    loop {
-        println!("{}", count_grid(&grid));
-        let newgrid: Array2<Seat> = if visibility {
-            update_by_visibility(&grid)
-        } else {
-            update_grid(&grid)
-        };
-        if newgrid == grid {
-            return newgrid;
-        } else {
-            grid = newgrid;
+/// Run the seating rules until they settle down, returning how they settled and the
+/// final seating. `name` identifies the rules when displaying them.
+fn simulate<N: Neighborhood>(
+    grid: &Array2<Seat>,
+    rules: &SeatRules<N>,
+    name: &str,
+    options: &Options,
+) -> io::Result<(Outcome, Array2<Seat>)> {
+    let delay = options
+        .animate
+        .unwrap_or_else(|| Duration::from_millis(100));
+    let mut animation = options
+        .animate
+        .map(|delay| TerminalAnimation::new(io::stdout(), delay, Glyphs::default()));
+    let mut recorder = match &options.gif_prefix {
+        Some(prefix) => Some(GifRecorder::create(
+            &format!("{}-{}.gif", prefix, name),
+            grid,
+            4,
+            delay,
+        )?),
+        None => None,
+    };
+
+    let mut result: io::Result<()> = Ok(());
+    let mut show = |grid: &Array2<Seat>, caption: &str| -> io::Result<()> {
+        if let Some(animation) = &mut animation {
+            animation.draw(grid, caption)?;
         }
-    }
+        if let Some(recorder) = &mut recorder {
+            recorder.record(grid)?;
+        }
+        Ok(())
+    };
+
+    show(grid, "generation 0")?;
+    let mut sim = Simulation::new(grid, rules);
+    let outcome = run_until_stable(&mut sim, |stats, grid| {
+        if result.is_ok() {
+            let caption = format!(
+                "generation {}: {} occupied, {} changed",
+                stats.generation, stats.num_full, stats.num_changed
+            );
+            result = show(grid, &caption);
+        }
+    });
+    result?;
+    Ok((outcome, sim.grid().clone()))
 }
 
-fn main() {
-    let grid = lines_to_grid(&get_lines("input.txt"));
-    let final_grid = iterate_until_fixed_point(&grid, false);
-    println!("{} seats occupied by adjacency", count_grid(&final_grid));
+/// Run the rules called `name` (adjacency, visibility, knight or radius) and print
+/// how they settled.
+fn run_rules(grid: &Array2<Seat>, name: &str, options: &Options) -> io::Result<()> {
+    let (outcome, final_grid) = match name {
+        "adjacency" => simulate(grid, &ADJACENCY_RULES, name, options)?,
+        "visibility" => simulate(grid, &VISIBILITY_RULES, name, options)?,
+        "knight" => simulate(grid, &KNIGHT_RULES, name, options)?,
+        "radius" => simulate(grid, &RADIUS_RULES, name, options)?,
+        _ => return Err(usage_error(&format!("unknown rules: {}", name))),
+    };
+    println!("{:?} by {}", outcome, name);
+    println!("{} seats occupied by {}", count_grid(&final_grid), name);
+    Ok(())
+}
 
-    let final_grid = iterate_until_fixed_point(&grid, true);
-    println!("{} seats occupied by visibility", count_grid(&final_grid));
+fn main() -> io::Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = Options::from_args(&args)?;
+    let grid = lines_to_grid(&get_lines("input.txt"));
+    let names = match &options.rules {
+        Some(name) => vec![name.as_str()],
+        None => vec!["adjacency", "visibility"],
+    };
+    for name in names {
+        run_rules(&grid, name, &options)?;
+    }
+    Ok(())
 }
 
 #[test]
 fn test_visibility() {
-    let lines: Vec<String> = vec![
+    let lines: Vec<String> = [
         "L.LL.LL.LL",
         "LLLLLLL.LL",
         "L.L.L..L..",
@@ -180,7 +190,22 @@ fn test_visibility() {
     .collect();
 
     let grid = lines_to_grid(&lines);
-    let final_grid = iterate_until_fixed_point(&grid, true);
+    let (outcome, final_grid) =
+        simulate(&grid, &VISIBILITY_RULES, "visibility", &Options::default()).unwrap();
+    assert_eq!(outcome, Outcome::FixedPoint { steps: 6 });
     let count = count_grid(&final_grid);
     assert_eq!(count, 26);
+
+    let (outcome, final_grid) =
+        simulate(&grid, &ADJACENCY_RULES, "adjacency", &Options::default()).unwrap();
+    assert_eq!(outcome, Outcome::FixedPoint { steps: 5 });
+    let count = count_grid(&final_grid);
+    assert_eq!(count, 37);
+}
+
+#[test]
+fn test_unknown_rules() {
+    let grid = lines_to_grid(&["LL".to_string()]);
+    let err = run_rules(&grid, "bishop", &Options::default()).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
 }
@@ -0,0 +1,241 @@
+use crate::Seat;
+use gif::{Encoder, Frame, Repeat};
+use ndarray::{s, Array2};
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{self, Write};
+use std::thread;
+use std::time::Duration;
+
+/// The characters to draw each kind of seat with. The default is the puzzle's own
+/// encoding.
+#[derive(Debug, Clone, Copy)]
+pub struct Glyphs {
+    pub floor: char,
+    pub empty: char,
+    pub full: char,
+}
+
+impl Default for Glyphs {
+    fn default() -> Self {
+        Glyphs {
+            floor: '.',
+            empty: 'L',
+            full: '#',
+        }
+    }
+}
+
+impl Glyphs {
+    pub fn glyph(&self, seat: Seat) -> char {
+        match seat {
+            Seat::Floor => self.floor,
+            Seat::Empty => self.empty,
+            Seat::Full => self.full,
+        }
+    }
+}
+
+/// The part of a grid that came from the input, leaving off the padding of floor that
+/// `lines_to_grid` adds around it.
+fn unpadded(grid: &Array2<Seat>) -> ndarray::ArrayView2<'_, Seat> {
+    let (n_rows, n_cols) = grid.dim();
+    grid.slice(s![1..(n_rows - 1), 1..(n_cols - 1)])
+}
+
+/// Draw a grid as lines of text.
+pub fn grid_to_text(grid: &Array2<Seat>, glyphs: &Glyphs) -> String {
+    let mut text = String::new();
+    for row in unpadded(grid).outer_iter() {
+        text.extend(row.iter().map(|&seat| glyphs.glyph(seat)));
+        text.push('\n');
+    }
+    text
+}
+
+/// Shows each generation in a terminal, redrawing over the previous one and pausing
+/// between frames so that you can watch the seats fill up.
+pub struct TerminalAnimation<W: Write> {
+    out: W,
+    delay: Duration,
+    glyphs: Glyphs,
+}
+
+impl<W: Write> TerminalAnimation<W> {
+    pub fn new(out: W, delay: Duration, glyphs: Glyphs) -> Self {
+        TerminalAnimation { out, delay, glyphs }
+    }
+
+    pub fn draw(&mut self, grid: &Array2<Seat>, caption: &str) -> io::Result<()> {
+        // Move the cursor home and clear the screen, using ANSI escape codes
+        write!(self.out, "\x1b[H\x1b[2J")?;
+        write!(self.out, "{}", grid_to_text(grid, &self.glyphs))?;
+        writeln!(self.out, "{}", caption)?;
+        self.out.flush()?;
+        thread::sleep(self.delay);
+        Ok(())
+    }
+}
+
+/// The colors of floor, empty seats and full seats in image output, as RGB.
+const PALETTE: [u8; 9] = [0x40, 0x40, 0x40, 0x30, 0xa0, 0x30, 0xe0, 0x30, 0x30];
+
+fn palette_index(seat: Seat) -> u8 {
+    match seat {
+        Seat::Floor => 0,
+        Seat::Empty => 1,
+        Seat::Full => 2,
+    }
+}
+
+/// Records each generation as a frame of an animated GIF. The GIF is finished when
+/// the recorder is dropped.
+pub struct GifRecorder<W: Write> {
+    encoder: Encoder<W>,
+    width: u16,
+    height: u16,
+    scale: usize,
+    /// How long to show each frame, in hundredths of a second.
+    delay: u16,
+}
+
+impl GifRecorder<File> {
+    /// Start a GIF in a new file at `path`. The arguments are the same as for
+    /// `GifRecorder::new`.
+    pub fn create(
+        path: &str,
+        grid: &Array2<Seat>,
+        scale: usize,
+        delay: Duration,
+    ) -> io::Result<Self> {
+        let file = File::create(path)?;
+        GifRecorder::new(file, grid, scale, delay)
+    }
+}
+
+impl<W: Write> GifRecorder<W> {
+    /// Start a GIF written to `out`, for grids shaped like `grid`. Each cell is drawn
+    /// as a square `scale` pixels wide, and frames are shown for `delay` each (rounded
+    /// to the nearest hundredth of a second, as GIF requires, and cut off at about 11
+    /// minutes).
+    ///
+    /// A GIF can be at most 65535 pixels on a side, so this returns an error if the
+    /// grid is too big to draw at this scale.
+    pub fn new(out: W, grid: &Array2<Seat>, scale: usize, delay: Duration) -> io::Result<Self> {
+        let (n_rows, n_cols) = unpadded(grid).dim();
+        let width = gif_size(n_cols, scale, "wide")?;
+        let height = gif_size(n_rows, scale, "tall")?;
+        let mut encoder = Encoder::new(out, width, height, &PALETTE).map_err(to_io_error)?;
+        encoder.set_repeat(Repeat::Infinite).map_err(to_io_error)?;
+        Ok(GifRecorder {
+            encoder,
+            width,
+            height,
+            scale,
+            delay: gif_delay(delay),
+        })
+    }
+
+    pub fn record(&mut self, grid: &Array2<Seat>) -> io::Result<()> {
+        let view = unpadded(grid);
+        let mut pixels: Vec<u8> = Vec::with_capacity(self.width as usize * self.height as usize);
+        for row in view.outer_iter() {
+            let line: Vec<u8> = row
+                .iter()
+                .flat_map(|&seat| std::iter::repeat_n(palette_index(seat), self.scale))
+                .collect();
+            for _ in 0..self.scale {
+                pixels.extend_from_slice(&line);
+            }
+        }
+        let mut frame = Frame::from_indexed_pixels(self.width, self.height, &pixels, None);
+        frame.delay = self.delay;
+        self.encoder.write_frame(&frame).map_err(to_io_error)
+    }
+}
+
+/// The number of pixels across `cells` cells at this scale, if it fits in a GIF.
+fn gif_size(cells: usize, scale: usize, direction: &str) -> io::Result<u16> {
+    cells
+        .checked_mul(scale)
+        .and_then(|pixels| u16::try_from(pixels).ok())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "a grid {} cells {} is too big for a GIF at scale {}",
+                    cells, direction, scale
+                ),
+            )
+        })
+}
+
+/// A frame delay in the hundredths of a second that GIF uses, as close as it can get.
+fn gif_delay(delay: Duration) -> u16 {
+    u16::try_from((delay.as_millis() + 5) / 10).unwrap_or(u16::MAX)
+}
+
+fn to_io_error(err: gif::EncodingError) -> io::Error {
+    match err {
+        gif::EncodingError::Io(err) => err,
+        other => io::Error::other(other),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lines_to_grid;
+
+    #[test]
+    fn test_grid_to_text() {
+        let lines: Vec<String> = ["L.LL", "LLL."]
+            .iter()
+            .map(|&line| line.to_string())
+            .collect();
+        let mut grid = lines_to_grid(&lines);
+        grid[(1, 1)] = Seat::Full;
+        assert_eq!(grid_to_text(&grid, &Glyphs::default()), "#.LL\nLLL.\n");
+
+        let glyphs = Glyphs {
+            floor: ' ',
+            empty: '_',
+            full: 'o',
+        };
+        assert_eq!(grid_to_text(&grid, &glyphs), "o __\n___ \n");
+    }
+
+    #[test]
+    fn test_gif_recorder() {
+        let lines: Vec<String> = ["L.LL", "LLL."]
+            .iter()
+            .map(|&line| line.to_string())
+            .collect();
+        let mut grid = lines_to_grid(&lines);
+        let mut out: Vec<u8> = Vec::new();
+        {
+            let mut recorder =
+                GifRecorder::new(&mut out, &grid, 2, Duration::from_millis(100)).unwrap();
+            recorder.record(&grid).unwrap();
+            grid[(1, 1)] = Seat::Full;
+            recorder.record(&grid).unwrap();
+        }
+        assert!(out.starts_with(b"GIF89a"));
+        // The logical screen is 8 by 4 pixels, stored little-endian
+        assert_eq!(&out[6..10], &[8, 0, 4, 0]);
+        assert_eq!(out.last(), Some(&0x3b));
+
+        assert_eq!(gif_delay(Duration::from_millis(100)), 10);
+        assert_eq!(gif_delay(Duration::from_millis(14)), 1);
+        assert_eq!(gif_delay(Duration::from_millis(15)), 2);
+        assert_eq!(gif_delay(Duration::from_secs(1000)), u16::MAX);
+
+        let wide: Vec<String> = vec![".".repeat(20000)];
+        let grid = lines_to_grid(&wide);
+        let result = GifRecorder::new(Vec::new(), &grid, 4, Duration::from_millis(100));
+        assert_eq!(
+            result.err().map(|err| err.kind()),
+            Some(io::ErrorKind::InvalidInput)
+        );
+    }
+}
@@ -0,0 +1,353 @@
+use crate::automaton::{Neighborhood, SeatRules};
+use crate::Seat;
+use ndarray::Array2;
+use std::collections::HashMap;
+
+/// A seating simulation that does the expensive work up front. Each seat's neighbors
+/// are found once, when the simulation is created, because the floor never changes.
+/// After that, each generation only revisits the seats that changed in the previous
+/// generation, and the seats that have one of them as a neighbor.
+pub struct Simulation<'a, N: Neighborhood> {
+    rules: &'a SeatRules<N>,
+    grid: Array2<Seat>,
+    /// The position of each seat in the grid. Seats are referred to by their index in
+    /// this list.
+    seats: Vec<(usize, usize)>,
+    /// The seats that each seat looks at.
+    neighbors: Vec<Vec<u32>>,
+    /// The seats that look at each seat.
+    watchers: Vec<Vec<u32>>,
+    /// The seats to revisit on the next generation.
+    frontier: Vec<u32>,
+    in_frontier: Vec<bool>,
+    num_full: usize,
+    /// The XOR of `seat_key` for every occupied seat, updated as seats change.
+    occupancy_hash: u64,
+    generation: usize,
+}
+
+/// A random-looking 64-bit key for each seat, for Zobrist hashing of which seats are
+/// occupied. It's computed from the seat's index with the SplitMix64 mixer, so it
+/// doesn't need to be stored.
+fn seat_key(id: u32) -> u64 {
+    let mut z = (id as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// Written out by hand, because deriving Clone would require the neighborhood to be
+// Clone too, and the simulation only holds a reference to it.
+impl<'a, N: Neighborhood> Clone for Simulation<'a, N> {
+    fn clone(&self) -> Self {
+        Simulation {
+            rules: self.rules,
+            grid: self.grid.clone(),
+            seats: self.seats.clone(),
+            neighbors: self.neighbors.clone(),
+            watchers: self.watchers.clone(),
+            frontier: self.frontier.clone(),
+            in_frontier: self.in_frontier.clone(),
+            num_full: self.num_full,
+            occupancy_hash: self.occupancy_hash,
+            generation: self.generation,
+        }
+    }
+}
+
+impl<'a, N: Neighborhood> Simulation<'a, N> {
+    pub fn new(grid: &Array2<Seat>, rules: &'a SeatRules<N>) -> Self {
+        let mut seat_ids: Array2<Option<u32>> = Array2::from_elem(grid.dim(), None);
+        let mut seats: Vec<(usize, usize)> = Vec::new();
+        for (pos, &seat) in grid.indexed_iter() {
+            if seat != Seat::Floor {
+                seat_ids[pos] = Some(seats.len() as u32);
+                seats.push(pos);
+            }
+        }
+
+        let neighbors: Vec<Vec<u32>> = seats
+            .iter()
+            .map(|&pos| {
+                rules
+                    .neighborhood
+                    .neighbors(grid, pos)
+                    .iter()
+                    .filter_map(|&neighbor| seat_ids[neighbor])
+                    .collect()
+            })
+            .collect();
+
+        let mut watchers: Vec<Vec<u32>> = vec![Vec::new(); seats.len()];
+        for (id, seat_neighbors) in neighbors.iter().enumerate() {
+            for &neighbor in seat_neighbors {
+                watchers[neighbor as usize].push(id as u32);
+            }
+        }
+
+        let occupancy_hash = seats
+            .iter()
+            .enumerate()
+            .filter(|&(_, &pos)| grid[pos] == Seat::Full)
+            .fold(0, |hash, (id, _)| hash ^ seat_key(id as u32));
+
+        Simulation {
+            rules,
+            grid: grid.clone(),
+            frontier: (0..seats.len() as u32).collect(),
+            in_frontier: vec![true; seats.len()],
+            num_full: grid.iter().filter(|&&seat| seat == Seat::Full).count(),
+            seats,
+            neighbors,
+            watchers,
+            occupancy_hash,
+            generation: 0,
+        }
+    }
+
+    /// The current state of the grid.
+    pub fn grid(&self) -> &Array2<Seat> {
+        &self.grid
+    }
+
+    /// How many seats are currently occupied.
+    pub fn num_full(&self) -> usize {
+        self.num_full
+    }
+
+    /// How many generations have been run.
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// A hash of which seats are occupied. Equal seatings always have equal hashes, and
+    /// different ones almost never do.
+    pub fn occupancy_hash(&self) -> u64 {
+        self.occupancy_hash
+    }
+
+    /// A compact snapshot of which seats are occupied, one bit per seat.
+    pub fn occupancy(&self) -> Vec<u64> {
+        let mut bits = vec![0u64; self.seats.len().div_ceil(64)];
+        for (id, &pos) in self.seats.iter().enumerate() {
+            if self.grid[pos] == Seat::Full {
+                bits[id / 64] |= 1 << (id % 64);
+            }
+        }
+        bits
+    }
+
+    fn seat(&self, id: u32) -> Seat {
+        self.grid[self.seats[id as usize]]
+    }
+
+    /// Run one generation, and return how many seats changed. If none changed, the
+    /// simulation has reached a fixed point.
+    pub fn step(&mut self) -> usize {
+        let frontier = std::mem::take(&mut self.frontier);
+        let mut changes: Vec<(u32, Seat)> = Vec::new();
+        for &id in &frontier {
+            self.in_frontier[id as usize] = false;
+            let seat = self.seat(id);
+            let num_full = self.neighbors[id as usize]
+                .iter()
+                .filter(|&&neighbor| self.seat(neighbor) == Seat::Full)
+                .count();
+            let new_seat = self.rules.next_seat(seat, num_full);
+            if new_seat != seat {
+                changes.push((id, new_seat));
+            }
+        }
+
+        for &(id, new_seat) in &changes {
+            let pos = self.seats[id as usize];
+            if new_seat == Seat::Full {
+                self.num_full += 1;
+            } else {
+                self.num_full -= 1;
+            }
+            // Seats only ever change between empty and full, so each change flips one
+            // seat in or out of the hash
+            self.occupancy_hash ^= seat_key(id);
+            self.grid[pos] = new_seat;
+
+            // A seat that changed could change again even if its neighbors don't, so it
+            // goes back on the frontier along with the seats watching it.
+            for &affected in std::iter::once(&id).chain(self.watchers[id as usize].iter()) {
+                if !self.in_frontier[affected as usize] {
+                    self.in_frontier[affected as usize] = true;
+                    self.frontier.push(affected);
+                }
+            }
+        }
+        self.generation += 1;
+        changes.len()
+    }
+}
+
+/// How a simulation ended up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// Nothing changed after this many generations.
+    FixedPoint { steps: usize },
+    /// The seating after generation `start` came back every `period` generations.
+    Cycle { start: usize, period: usize },
+}
+
+/// Statistics about one generation of a simulation, for watching its progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GenerationStats {
+    pub generation: usize,
+    pub num_full: usize,
+    pub num_changed: usize,
+}
+
+/// Run a simulation until it reaches a fixed point or repeats an earlier seating,
+/// calling `on_generation` with statistics and the new grid after each generation.
+///
+/// Only a hash of each seating seen so far is remembered, so cycles of any period are
+/// found as soon as they complete without keeping every seating around. The hash is
+/// kept up to date by the simulation itself, so checking it doesn't visit every seat.
+/// When a hash matches, the earlier seating is rebuilt by replaying a copy of the
+/// simulation from where it started, to make sure it really is the same.
+pub fn run_until_stable<N: Neighborhood>(
+    sim: &mut Simulation<N>,
+    mut on_generation: impl FnMut(&GenerationStats, &Array2<Seat>),
+) -> Outcome {
+    let initial = sim.clone();
+    let mut seen: HashMap<u64, Vec<usize>> = HashMap::new();
+    seen.insert(sim.occupancy_hash(), vec![sim.generation()]);
+    loop {
+        let num_changed = sim.step();
+        let stats = GenerationStats {
+            generation: sim.generation(),
+            num_full: sim.num_full(),
+            num_changed,
+        };
+        on_generation(&stats, sim.grid());
+        if num_changed == 0 {
+            return Outcome::FixedPoint {
+                steps: sim.generation() - 1,
+            };
+        }
+        let generations = seen.entry(sim.occupancy_hash()).or_default();
+        for &start in generations.iter() {
+            let mut replay = initial.clone();
+            while replay.generation() < start {
+                replay.step();
+            }
+            if replay.occupancy() == sim.occupancy() {
+                return Outcome::Cycle {
+                    start,
+                    period: sim.generation() - start,
+                };
+            }
+        }
+        generations.push(sim.generation());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::automaton::{Adjacent, KnightMoves, Radius, ADJACENCY_RULES, VISIBILITY_RULES};
+    use crate::lines_to_grid;
+
+    /// Check that the simulation matches running the rules over the whole grid.
+    fn check_against_full_steps<N: Neighborhood>(grid: &Array2<Seat>, rules: &SeatRules<N>) {
+        let mut sim = Simulation::new(grid, rules);
+        let mut expected = grid.clone();
+        for _ in 0..20 {
+            expected = rules.step(&expected);
+            sim.step();
+            assert_eq!(sim.grid(), &expected);
+            assert_eq!(
+                sim.occupancy_hash(),
+                Simulation::new(&expected, rules).occupancy_hash()
+            );
+            assert_eq!(
+                sim.num_full(),
+                expected.iter().filter(|&&seat| seat == Seat::Full).count()
+            );
+        }
+    }
+
+    #[test]
+    fn test_matches_full_steps() {
+        let lines: Vec<String> = [
+            "L.LL.LL.LL",
+            "LLLLLLL.LL",
+            "L.L.L..L..",
+            "LLLL.LL.LL",
+            "L.LL.LL.LL",
+            "L.LLLLL.LL",
+            "..L.L.....",
+            "LLLLLLLLLL",
+            "L.LLLLLL.L",
+            "L.LLLLL.LL",
+        ]
+        .iter()
+        .map(|&line| line.to_string())
+        .collect();
+        let grid = lines_to_grid(&lines);
+
+        check_against_full_steps(&grid, &ADJACENCY_RULES);
+        check_against_full_steps(&grid, &VISIBILITY_RULES);
+        check_against_full_steps(
+            &grid,
+            &SeatRules {
+                neighborhood: KnightMoves,
+                max_to_sit: 1,
+                min_to_leave: 3,
+            },
+        );
+        check_against_full_steps(
+            &grid,
+            &SeatRules {
+                neighborhood: Radius(2),
+                max_to_sit: 2,
+                min_to_leave: 2,
+            },
+        );
+    }
+
+    #[test]
+    fn test_outcomes() {
+        let grid = lines_to_grid(&["LL".to_string()]);
+        let mut sim = Simulation::new(&grid, &ADJACENCY_RULES);
+        let mut history: Vec<GenerationStats> = Vec::new();
+        let outcome = run_until_stable(&mut sim, |stats, _| history.push(*stats));
+        assert_eq!(outcome, Outcome::FixedPoint { steps: 1 });
+        assert_eq!(history[0].num_full, 2);
+        assert_eq!(history[0].num_changed, 2);
+        assert_eq!(history[1].num_changed, 0);
+
+        // With these rules, neighbors keep getting up and sitting down together
+        let restless = SeatRules {
+            neighborhood: Adjacent,
+            max_to_sit: 0,
+            min_to_leave: 1,
+        };
+        let mut sim = Simulation::new(&grid, &restless);
+        let outcome = run_until_stable(&mut sim, |_, _| {});
+        assert_eq!(
+            outcome,
+            Outcome::Cycle {
+                start: 0,
+                period: 2
+            }
+        );
+
+        // Starting partway through, the cycle is found from there
+        let mut sim = Simulation::new(&grid, &restless);
+        sim.step();
+        let outcome = run_until_stable(&mut sim, |_, _| {});
+        assert_eq!(
+            outcome,
+            Outcome::Cycle {
+                start: 1,
+                period: 2
+            }
+        );
+    }
+}
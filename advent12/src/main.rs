@@ -1,15 +1,61 @@
 mod helpers;
+mod navigator;
+mod track;
 use helpers::get_lines;
+use navigator::{all_navigators, navigator_by_name};
+#[cfg(test)]
+use navigator::{HeadingNavigator, Navigator, WaypointNavigator};
+use track::Track;
 
 use self::Movement::*;
 use scan_fmt::scan_fmt;
+use std::env;
+use std::fs;
+use std::io;
 
-#[derive(Debug)]
+/// The position of the ship, and either its heading or its waypoint, as a vector
+/// (dx, dy) relative to the ship.
+///
+/// Coordinates are f64 so that the ship can turn by any angle. See `rotate` for how
+/// rounding works.
+#[derive(Debug, Clone, Copy, PartialEq)]
 struct ShipState {
-    x: i64,
-    y: i64,
-    dx: i64,
-    dy: i64,
+    x: f64,
+    y: f64,
+    dx: f64,
+    dy: f64,
+}
+
+impl ShipState {
+    /// The Manhattan distance of the ship from the origin, rounded to the nearest integer.
+    fn manhattan_distance(&self) -> i64 {
+        (self.x.abs() + self.y.abs()).round() as i64
+    }
+
+    /// Rotate the heading or waypoint around the ship.
+    fn turned(self, angle: i64) -> ShipState {
+        let (dx, dy) = rotate(self.dx, self.dy, angle);
+        ShipState { dx, dy, ..self }
+    }
+}
+
+/// Rotate the vector (dx, dy) counterclockwise by `angle` degrees.
+///
+/// The rounding policy is that turns by multiples of 90 degrees are exact, because they
+/// just swap and negate the components, so the puzzle's own instructions never pick up
+/// any floating-point error. Other angles use f64 trigonometry, and the results are not
+/// rounded at all until a distance is reported by `manhattan_distance`.
+fn rotate(dx: f64, dy: f64, angle: i64) -> (f64, f64) {
+    match angle.rem_euclid(360) {
+        0 => (dx, dy),
+        90 => (-dy, dx),
+        180 => (-dx, -dy),
+        270 => (dy, -dx),
+        degrees => {
+            let (sin, cos) = (degrees as f64).to_radians().sin_cos();
+            (dx * cos - dy * sin, dx * sin + dy * cos)
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -37,150 +83,93 @@ fn parse_movement(input: &str) -> Movement {
     }
 }
 
-fn apply_movement(mvt: Movement, state: ShipState) -> ShipState {
-    match mvt {
-        Forward(dist) => ShipState {
-            x: state.x + state.dx * dist,
-            y: state.y + state.dy * dist,
-            dx: state.dx,
-            dy: state.dy,
-        },
-        MoveX(dist) => ShipState {
-            x: state.x + dist,
-            y: state.y,
-            dx: state.dx,
-            dy: state.dy,
-        },
-        MoveY(dist) => ShipState {
-            x: state.x,
-            y: state.y + dist,
-            dx: state.dx,
-            dy: state.dy,
-        },
-        Turn(angle) => {
-            let normalized_angle = angle.rem_euclid(360);
-            match normalized_angle {
-                0 => state,
-                90 => ShipState {
-                    x: state.x,
-                    y: state.y,
-                    dx: -state.dy,
-                    dy: state.dx,
-                },
-                180 => ShipState {
-                    x: state.x,
-                    y: state.y,
-                    dx: -state.dx,
-                    dy: -state.dy,
-                },
-                270 => ShipState {
-                    x: state.x,
-                    y: state.y,
-                    dx: state.dy,
-                    dy: -state.dx,
-                },
-                _ => panic!("weird angle: {}", angle),
+/// Write a track to `{name}.{format}`, where the format is csv, geojson or svg.
+fn export_track(track: &Track, name: &str, format: &str) -> io::Result<()> {
+    let contents = match format {
+        "csv" => track.to_csv(),
+        "geojson" => track.to_geojson(),
+        "svg" => track.to_svg(),
+        _ => return Err(usage_error(&format!("unknown export format: {}", format))),
+    };
+    fs::write(format!("{}.{}", name, format), contents)
+}
+
+fn usage_error(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+fn main() -> io::Result<()> {
+    // `--mode NAME` picks one navigator instead of running all of them, and
+    // `--export FORMAT` writes each track to a file as well
+    let mut navigators = all_navigators();
+    let mut export_format: Option<String> = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--mode" => {
+                let name = args
+                    .next()
+                    .ok_or_else(|| usage_error("--mode needs a navigator name"))?;
+                let navigator = navigator_by_name(&name)
+                    .ok_or_else(|| usage_error(&format!("unknown navigation mode: {}", name)))?;
+                navigators = vec![navigator];
             }
+            "--export" => {
+                let format = args
+                    .next()
+                    .ok_or_else(|| usage_error("--export needs a format"))?;
+                export_format = Some(format);
+            }
+            other => return Err(usage_error(&format!("unknown option: {}", other))),
         }
     }
-}
 
-fn apply_waypoint_move(mvt: Movement, state: ShipState) -> ShipState {
-    match mvt {
-        Forward(dist) => ShipState {
-            x: state.x + state.dx * dist,
-            y: state.y + state.dy * dist,
-            dx: state.dx,
-            dy: state.dy,
-        },
-        MoveX(dist) => ShipState {
-            x: state.x,
-            y: state.y,
-            dx: state.dx + dist,
-            dy: state.dy,
-        },
-        MoveY(dist) => ShipState {
-            x: state.x,
-            y: state.y,
-            dx: state.dx,
-            dy: state.dy + dist,
-        },
-        Turn(angle) => {
-            let normalized_angle = angle.rem_euclid(360);
-            match normalized_angle {
-                0 => state,
-                90 => ShipState {
-                    x: state.x,
-                    y: state.y,
-                    dx: -state.dy,
-                    dy: state.dx,
-                },
-                180 => ShipState {
-                    x: state.x,
-                    y: state.y,
-                    dx: -state.dx,
-                    dy: -state.dy,
-                },
-                270 => ShipState {
-                    x: state.x,
-                    y: state.y,
-                    dx: state.dy,
-                    dy: -state.dx,
-                },
-                _ => panic!("weird angle: {}", angle),
-            }
+    let lines = get_lines("input.txt");
+    let moves: Vec<Movement> = lines.iter().map(|line| parse_movement(line)).collect();
+    for navigator in navigators {
+        let name = navigator.name();
+        let track = navigator.navigate(&moves);
+        let dist = track.last().manhattan_distance();
+        println!("Distance moved with {} instructions: {}", name, dist);
+        println!("{:?}", track.stats());
+        if let Some(format) = &export_format {
+            export_track(&track, &format!("track-{}", name), format)?;
         }
     }
+    Ok(())
 }
 
+#[test]
+fn test_example() {
+    let moves = vec![Forward(10), MoveY(3), Forward(7), Turn(-90), Forward(11)];
+    let state = HeadingNavigator.navigate(&moves).last();
+    assert_eq!(state.x, 17.0);
+    assert_eq!(state.y, -8.0);
 
-fn apply_moves_basic(moves: &[Movement]) -> ShipState {
-    let mut state = ShipState {
-        x: 0,
-        y: 0,
-        dx: 1,
-        dy: 0,
-    };
-    for &mvt in moves {
-        state = apply_movement(mvt, state);
-    }
-    state
+    let state = WaypointNavigator.navigate(&moves).last();
+    assert_eq!(state.x, 214.0);
+    assert_eq!(state.y, -72.0);
 }
 
-fn apply_moves_waypoint(moves: &[Movement]) -> ShipState {
-    let mut state = ShipState {
-        x: 0,
-        y: 0,
-        dx: 10,
-        dy: 1,
-    };
-    for &mvt in moves {
-        state = apply_waypoint_move(mvt, state);
-        println!("{:?}", state);
-    }
-    state
-}
+#[test]
+fn test_arbitrary_angles() {
+    let moves: Vec<Movement> = ["L45", "F10", "R90", "F10"]
+        .iter()
+        .map(|line| parse_movement(line))
+        .collect();
+    let state = HeadingNavigator.navigate(&moves).last();
+    assert!((state.x - 10.0 * 2f64.sqrt()).abs() < 1e-9);
+    assert!(state.y.abs() < 1e-9);
+    assert_eq!(state.manhattan_distance(), 14);
 
-fn main() {
-    let lines = get_lines("input.txt");
-    let moves: Vec<Movement> = lines.iter().map(|line| parse_movement(&line)).collect();
-    let newstate = apply_moves_basic(&moves);
-    let dist = newstate.x.abs() + newstate.y.abs();
-    println!("Distance moved with basic instructions: {}", dist);
-
-    let newstate = apply_moves_waypoint(&moves);
-    let dist = newstate.x.abs() + newstate.y.abs();
-    println!("Distance moved with waypoint instructions: {}", dist);
+    // Turning all the way around in odd steps ends up back where we started
+    let (dx, dy) = (0..8).fold((10.0, 1.0), |(dx, dy), _| rotate(dx, dy, 45));
+    assert!((dx - 10.0).abs() < 1e-9 && (dy - 1.0).abs() < 1e-9);
 }
 
 #[test]
-fn test_example() {
-    let moves = vec![Forward(10), MoveY(3), Forward(7), Turn(-90), Forward(11)];
-    let state = apply_moves_basic(&moves);
-    assert_eq!(state.x, 17);
-    assert_eq!(state.y, -8);
-
-    let state = apply_moves_waypoint(&moves);
-    assert_eq!(state.x, 214);
-    assert_eq!(state.y, -72);
+fn test_unknown_export_format() {
+    let track = HeadingNavigator.navigate(&[Forward(10)]);
+    let err = export_track(&track, "unused", "png").unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
 }
@@ -0,0 +1,167 @@
+use crate::track::Track;
+use crate::Movement::{self, *};
+use crate::ShipState;
+
+/// A way of interpreting navigation instructions. Each navigator decides where the
+/// ship starts and what each movement does to it.
+pub trait Navigator {
+    /// The name used to select this navigator on the command line.
+    fn name(&self) -> &'static str;
+
+    fn start(&self) -> ShipState;
+
+    fn apply(&self, mvt: Movement, state: ShipState) -> ShipState;
+
+    /// Follow a list of instructions from the start, recording the whole track.
+    fn navigate(&self, moves: &[Movement]) -> Track {
+        let mut state = self.start();
+        let mut track = Track::new(state);
+        for &mvt in moves {
+            state = self.apply(mvt, state);
+            track.push(state);
+        }
+        track
+    }
+}
+
+/// Move the ship forward along its heading.
+fn forward(state: ShipState, dist: i64) -> ShipState {
+    ShipState {
+        x: state.x + state.dx * dist as f64,
+        y: state.y + state.dy * dist as f64,
+        ..state
+    }
+}
+
+/// Part 1 of the puzzle: N/S/E/W move the ship, and L/R turn its heading, which
+/// starts out facing east.
+pub struct HeadingNavigator;
+
+impl Navigator for HeadingNavigator {
+    fn name(&self) -> &'static str {
+        "heading"
+    }
+
+    fn start(&self) -> ShipState {
+        ShipState {
+            x: 0.0,
+            y: 0.0,
+            dx: 1.0,
+            dy: 0.0,
+        }
+    }
+
+    fn apply(&self, mvt: Movement, state: ShipState) -> ShipState {
+        match mvt {
+            Forward(dist) => forward(state, dist),
+            MoveX(dist) => ShipState {
+                x: state.x + dist as f64,
+                ..state
+            },
+            MoveY(dist) => ShipState {
+                y: state.y + dist as f64,
+                ..state
+            },
+            Turn(angle) => state.turned(angle),
+        }
+    }
+}
+
+/// Part 2 of the puzzle: N/S/E/W move a waypoint, which starts 10 east and 1 north
+/// of the ship, and L/R rotate the waypoint around the ship.
+pub struct WaypointNavigator;
+
+impl Navigator for WaypointNavigator {
+    fn name(&self) -> &'static str {
+        "waypoint"
+    }
+
+    fn start(&self) -> ShipState {
+        ShipState {
+            x: 0.0,
+            y: 0.0,
+            dx: 10.0,
+            dy: 1.0,
+        }
+    }
+
+    fn apply(&self, mvt: Movement, state: ShipState) -> ShipState {
+        match mvt {
+            Forward(dist) => forward(state, dist),
+            MoveX(dist) => ShipState {
+                dx: state.dx + dist as f64,
+                ..state
+            },
+            MoveY(dist) => ShipState {
+                dy: state.dy + dist as f64,
+                ..state
+            },
+            Turn(angle) => state.turned(angle),
+        }
+    }
+}
+
+/// Like `HeadingNavigator`, except that N/S/E/W are relative to the ship's heading:
+/// N moves ahead, S moves astern, E moves to starboard and W moves to port, all
+/// without turning.
+pub struct RelativeNavigator;
+
+impl Navigator for RelativeNavigator {
+    fn name(&self) -> &'static str {
+        "relative"
+    }
+
+    fn start(&self) -> ShipState {
+        HeadingNavigator.start()
+    }
+
+    fn apply(&self, mvt: Movement, state: ShipState) -> ShipState {
+        match mvt {
+            Forward(dist) | MoveY(dist) => forward(state, dist),
+            MoveX(dist) => forward(state.turned(-90), dist).turned(90),
+            Turn(angle) => state.turned(angle),
+        }
+    }
+}
+
+/// Every navigator, in the order they should be run.
+pub fn all_navigators() -> Vec<Box<dyn Navigator>> {
+    vec![
+        Box::new(HeadingNavigator),
+        Box::new(WaypointNavigator),
+        Box::new(RelativeNavigator),
+    ]
+}
+
+/// Look up a navigator by its name.
+pub fn navigator_by_name(name: &str) -> Option<Box<dyn Navigator>> {
+    all_navigators()
+        .into_iter()
+        .find(|navigator| navigator.name() == name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_relative() {
+        let moves = vec![
+            Forward(10),
+            MoveY(3),
+            Forward(7),
+            Turn(-90),
+            Forward(11),
+            MoveX(2),
+        ];
+        let state = RelativeNavigator.navigate(&moves).last();
+        assert_eq!((state.x, state.y), (18.0, -11.0));
+        assert_eq!((state.dx, state.dy), (0.0, -1.0));
+    }
+
+    #[test]
+    fn test_by_name() {
+        assert_eq!(navigator_by_name("waypoint").unwrap().name(), "waypoint");
+        assert!(navigator_by_name("submarine").is_none());
+    }
+}
@@ -0,0 +1,147 @@
+use crate::ShipState;
+use std::fmt::Write;
+
+/// Every state the ship was in while navigating: the starting state, then the state
+/// after each instruction.
+#[derive(Debug, Clone)]
+pub struct Track {
+    pub states: Vec<ShipState>,
+}
+
+/// Summary statistics of a track. Distances here are straight-line distances, unlike
+/// the Manhattan distance that the puzzle asks for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrackStats {
+    pub total_distance: f64,
+    pub min_x: f64,
+    pub max_x: f64,
+    pub min_y: f64,
+    pub max_y: f64,
+    pub max_distance_from_origin: f64,
+}
+
+impl Track {
+    pub fn new(start: ShipState) -> Self {
+        Track {
+            states: vec![start],
+        }
+    }
+
+    pub fn push(&mut self, state: ShipState) {
+        self.states.push(state);
+    }
+
+    /// The state after the last instruction.
+    pub fn last(&self) -> ShipState {
+        *self.states.last().unwrap()
+    }
+
+    pub fn stats(&self) -> TrackStats {
+        let first = self.states[0];
+        let mut stats = TrackStats {
+            total_distance: 0.0,
+            min_x: first.x,
+            max_x: first.x,
+            min_y: first.y,
+            max_y: first.y,
+            max_distance_from_origin: first.x.hypot(first.y),
+        };
+        for pair in self.states.windows(2) {
+            let (prev, state) = (pair[0], pair[1]);
+            stats.total_distance += (state.x - prev.x).hypot(state.y - prev.y);
+            stats.min_x = stats.min_x.min(state.x);
+            stats.max_x = stats.max_x.max(state.x);
+            stats.min_y = stats.min_y.min(state.y);
+            stats.max_y = stats.max_y.max(state.y);
+            stats.max_distance_from_origin =
+                stats.max_distance_from_origin.max(state.x.hypot(state.y));
+        }
+        stats
+    }
+
+    /// One row per state, with a header row. `dx` and `dy` are the heading or the
+    /// waypoint, depending on how the ship was navigated.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("step,x,y,dx,dy\n");
+        for (step, state) in self.states.iter().enumerate() {
+            writeln!(
+                csv,
+                "{},{},{},{},{}",
+                step, state.x, state.y, state.dx, state.dy
+            )
+            .unwrap();
+        }
+        csv
+    }
+
+    /// The ship's positions as a GeoJSON Feature containing a LineString. The ship's
+    /// x and y are used directly as longitude and latitude.
+    pub fn to_geojson(&self) -> String {
+        let coords: Vec<String> = self
+            .states
+            .iter()
+            .map(|state| format!("[{},{}]", state.x, state.y))
+            .collect();
+        format!(
+            "{{\"type\":\"Feature\",\"properties\":{{}},\"geometry\":{{\"type\":\"LineString\",\"coordinates\":[{}]}}}}\n",
+            coords.join(",")
+        )
+    }
+
+    /// The ship's positions as an SVG polyline, in a viewBox that fits the whole track.
+    /// SVG's y axis points down, so north is flipped to point up.
+    pub fn to_svg(&self) -> String {
+        let stats = self.stats();
+        let width = (stats.max_x - stats.min_x).max(1.0);
+        let height = (stats.max_y - stats.min_y).max(1.0);
+        let margin = width.max(height) / 20.0;
+        let points: Vec<String> = self
+            .states
+            .iter()
+            .map(|state| format!("{},{}", state.x, 0.0 - state.y))
+            .collect();
+        format!(
+            concat!(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">\n",
+                "  <polyline fill=\"none\" stroke=\"black\" stroke-width=\"{}\" points=\"{}\"/>\n",
+                "</svg>\n"
+            ),
+            stats.min_x - margin,
+            -stats.max_y - margin,
+            width + margin * 2.0,
+            height + margin * 2.0,
+            margin / 5.0,
+            points.join(" ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::navigator::{HeadingNavigator, Navigator};
+    use crate::Movement::*;
+
+    #[test]
+    fn test_track() {
+        let moves = vec![Forward(10), MoveY(3), Forward(7), Turn(-90), Forward(11)];
+        let track = HeadingNavigator.navigate(&moves);
+        assert_eq!(track.states.len(), 6);
+
+        let stats = track.stats();
+        assert_eq!(stats.total_distance, 31.0);
+        assert_eq!((stats.min_x, stats.max_x), (0.0, 17.0));
+        assert_eq!((stats.min_y, stats.max_y), (-8.0, 3.0));
+        assert_eq!(stats.max_distance_from_origin, 17.0f64.hypot(8.0));
+
+        let csv = track.to_csv();
+        assert_eq!(csv.lines().count(), 7);
+        assert_eq!(csv.lines().nth(3), Some("2,10,3,1,0"));
+
+        assert!(track
+            .to_geojson()
+            .contains("\"coordinates\":[[0,0],[10,0],[10,3],[17,3],[17,3],[17,-8]]"));
+        assert!(track
+            .to_svg()
+            .contains("points=\"0,0 10,0 10,-3 17,-3 17,-3 17,8\""));
+    }
+}
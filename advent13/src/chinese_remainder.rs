@@ -1,8 +1,40 @@
-// Coding is knowing what to google. This is the algorithm to solve the Chinese Remainder
-// Theorem for co-prime numbers, provided by
-// https://rosettacode.org/wiki/Chinese_remainder_theorem#Rust.
+// A generalized Chinese Remainder Theorem solver. The original version of this came
+// from https://rosettacode.org/wiki/Chinese_remainder_theorem#Rust, which only works
+// when the moduli are co-prime. This one merges the congruences one pair at a time
+// using their gcd, so it works for any moduli as long as the system is consistent.
 
-fn egcd(a: i64, b: i64) -> (i64, i64, i64) {
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrtError {
+    /// A modulus was zero or negative.
+    BadModulus { index: usize, modulus: i128 },
+    /// The congruence at this index can't be satisfied along with the ones before it.
+    Inconsistent { index: usize },
+    /// The combined modulus doesn't fit in an i128.
+    Overflow,
+}
+
+impl fmt::Display for CrtError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CrtError::BadModulus { index, modulus } => {
+                write!(f, "modulus {} at index {} is not positive", modulus, index)
+            }
+            CrtError::Inconsistent { index } => write!(
+                f,
+                "congruence at index {} contradicts the ones before it",
+                index
+            ),
+            CrtError::Overflow => write!(f, "combined modulus is too large for an i128"),
+        }
+    }
+}
+
+impl Error for CrtError {}
+
+fn egcd(a: i128, b: i128) -> (i128, i128, i128) {
     if a == 0 {
         (b, 0, 1)
     } else {
@@ -11,25 +43,123 @@ fn egcd(a: i64, b: i64) -> (i64, i64, i64) {
     }
 }
 
-fn mod_inv(x: i64, n: i64) -> Option<i64> {
+fn mod_inv(x: i128, n: i128) -> Option<i128> {
     let (g, x, _) = egcd(x, n);
     if g == 1 {
-        Some((x % n + n) % n)
+        Some(x.rem_euclid(n))
     } else {
         None
     }
 }
 
-pub fn chinese_remainder(residues: &[i64], modulii: &[i64]) -> Option<i64> {
-    let prod = modulii.iter().product::<i64>();
+/// Multiply a and b modulo m, where a and b are already reduced modulo m, without
+/// overflowing even when m is close to the limit of an i128.
+fn mul_mod(a: i128, b: i128, m: i128) -> i128 {
+    if let Some(product) = a.checked_mul(b) {
+        return product.rem_euclid(m);
+    }
+    // Fall back on multiplying by doubling and adding
+    let (mut a, mut b) = (a, b);
+    let mut result: i128 = 0;
+    while b > 0 {
+        if b & 1 == 1 {
+            result = add_mod(result, a, m);
+        }
+        a = add_mod(a, a, m);
+        b >>= 1;
+    }
+    result
+}
 
-    let mut sum = 0;
+/// Add a and b modulo m, where a and b are already reduced modulo m.
+fn add_mod(a: i128, b: i128, m: i128) -> i128 {
+    if a >= m - b {
+        a - (m - b)
+    } else {
+        a + b
+    }
+}
 
-    for (&residue, &modulus) in residues.iter().zip(modulii) {
-        let p = prod / modulus;
-        sum += residue * mod_inv(p, modulus)? * p
+/// Find the x that satisfies x ≡ residues[i] (mod moduli[i]) for every i. The
+/// solution is returned along with the combined modulus (the lcm of all the moduli),
+/// so every solution is x plus a multiple of that modulus; x is the smallest
+/// non-negative one.
+pub fn chinese_remainder(residues: &[i128], moduli: &[i128]) -> Result<(i128, i128), CrtError> {
+    let mut solution: i128 = 0;
+    let mut combined: i128 = 1;
+
+    for (index, (&residue, &modulus)) in residues.iter().zip(moduli).enumerate() {
+        if modulus <= 0 {
+            return Err(CrtError::BadModulus { index, modulus });
+        }
+        let residue = residue.rem_euclid(modulus);
+        let (g, _, _) = egcd(combined, modulus);
+        let diff = residue - solution.rem_euclid(modulus);
+        if diff % g != 0 {
+            return Err(CrtError::Inconsistent { index });
+        }
+
+        // Solve solution + combined * k ≡ residue (mod modulus) for k, working modulo
+        // modulus / g where combined / g has an inverse.
+        let reduced_modulus = modulus / g;
+        let inverse = mod_inv((combined / g).rem_euclid(reduced_modulus), reduced_modulus)
+            .expect("dividing out the gcd leaves co-prime numbers");
+        let k = mul_mod(
+            (diff / g).rem_euclid(reduced_modulus),
+            inverse,
+            reduced_modulus,
+        );
+
+        let new_combined = combined
+            .checked_mul(reduced_modulus)
+            .ok_or(CrtError::Overflow)?;
+        // combined * k < new_combined, so this can't overflow
+        solution = (solution + combined * k).rem_euclid(new_combined);
+        combined = new_combined;
+    }
+
+    Ok((solution, combined))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_coprime() {
+        assert_eq!(chinese_remainder(&[2, 3, 2], &[3, 5, 7]), Ok((23, 105)));
+    }
+
+    #[test]
+    fn test_not_coprime() {
+        assert_eq!(chinese_remainder(&[2, 4], &[4, 6]), Ok((10, 12)));
+        assert_eq!(chinese_remainder(&[3, 3, -1], &[6, 9, 4]), Ok((3, 36)));
+        assert_eq!(
+            chinese_remainder(&[1, 2], &[4, 6]),
+            Err(CrtError::Inconsistent { index: 1 })
+        );
+        assert_eq!(
+            chinese_remainder(&[1, 2], &[4, 0]),
+            Err(CrtError::BadModulus {
+                index: 1,
+                modulus: 0
+            })
+        );
     }
 
-    // changed from % because we need a positive solution
-    Some(sum.rem_euclid(prod))
+    #[test]
+    fn test_large() {
+        // These two primes multiply to more than an i64 can hold
+        let p: i128 = 4_294_967_311;
+        let q: i128 = 4_294_967_357;
+        let (x, m) = chinese_remainder(&[1, 2], &[p, q]).unwrap();
+        assert_eq!(m, p * q);
+        assert_eq!((x % p, x % q), (1, 2));
+
+        let big: i128 = 1 << 100;
+        assert_eq!(
+            chinese_remainder(&[0, 0], &[big, big + 1]),
+            Err(CrtError::Overflow)
+        );
+    }
 }
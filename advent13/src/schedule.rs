@@ -0,0 +1,313 @@
+use crate::chinese_remainder::{chinese_remainder, CrtError};
+use std::error::Error;
+use std::fmt;
+
+/// A list of buses, in the order the puzzle gives them. Positions marked `x` have no
+/// bus, but they still count when working out each bus's offset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    pub slots: Vec<Option<i64>>,
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct BusResult {
+    pub bus_number: i64,
+    pub wait_time: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The input didn't have a line that it needed.
+    MissingLine,
+    /// There was nothing between two commas.
+    EmptyEntry,
+    /// The token wasn't a number, or `x`.
+    NotANumber,
+    /// The earliest departure time wasn't a number.
+    NotATime,
+    /// A bus ID was zero or negative.
+    NotPositive,
+    /// The earliest departure time was negative.
+    Negative,
+    /// There was more input after the bus list.
+    ExtraLine,
+}
+
+/// A problem with a schedule file, pointing to the line and column (both starting at
+/// 1) of the token that caused it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub token: String,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let problem = match self.kind {
+            ParseErrorKind::MissingLine => "missing line",
+            ParseErrorKind::EmptyEntry => "empty entry",
+            ParseErrorKind::NotANumber => "expected a bus ID or x",
+            ParseErrorKind::NotATime => "expected a departure time",
+            ParseErrorKind::NotPositive => "bus IDs must be positive",
+            ParseErrorKind::Negative => "times can't be negative",
+            ParseErrorKind::ExtraLine => "unexpected input after the bus list",
+        };
+        write!(
+            f,
+            "line {}, column {}: {} (found {:?})",
+            self.line, self.column, problem, self.token
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+/// Split a line on commas, giving each trimmed token with the column where it starts.
+fn tokens(line: &str) -> Vec<(usize, &str)> {
+    let mut result = Vec::new();
+    let mut start = 0;
+    for piece in line.split(',') {
+        let leading = piece.len() - piece.trim_start().len();
+        let column = line[..(start + leading)].chars().count() + 1;
+        result.push((column, piece.trim()));
+        start += piece.len() + 1;
+    }
+    result
+}
+
+/// Parse a number that's at least `min`, reporting where it went wrong if it isn't one.
+fn parse_at_least(min: i64, token: &str, line: usize, column: usize) -> Result<i64, ParseError> {
+    let error = |kind| ParseError {
+        line,
+        column,
+        token: token.to_string(),
+        kind,
+    };
+    if token.is_empty() {
+        return Err(error(ParseErrorKind::EmptyEntry));
+    }
+    match token.parse::<i64>() {
+        Ok(num) if num >= min => Ok(num),
+        Ok(_) if min > 0 => Err(error(ParseErrorKind::NotPositive)),
+        Ok(_) => Err(error(ParseErrorKind::Negative)),
+        Err(_) => Err(error(ParseErrorKind::NotANumber)),
+    }
+}
+
+/// Parse a whole puzzle input: the earliest time you could leave on the first line, and
+/// the bus list on the second. Blank lines around them are ignored, but anything else
+/// after the bus list is an error.
+pub fn parse_input(text: &str) -> Result<(i64, Schedule), ParseError> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line))
+        .filter(|(_, line)| !line.trim().is_empty());
+    let missing = |line| ParseError {
+        line,
+        column: 1,
+        token: String::new(),
+        kind: ParseErrorKind::MissingLine,
+    };
+
+    let (time_line, time_text) = lines.next().ok_or_else(|| missing(1))?;
+    let leading = time_text.len() - time_text.trim_start().len();
+    let start_time =
+        parse_at_least(0, time_text.trim(), time_line, leading + 1).map_err(|err| {
+            match err.kind {
+                ParseErrorKind::NotANumber => ParseError {
+                    kind: ParseErrorKind::NotATime,
+                    ..err
+                },
+                _ => err,
+            }
+        })?;
+    let (bus_line, bus_text) = lines.next().ok_or_else(|| missing(time_line + 1))?;
+    let schedule = Schedule::parse(bus_text).map_err(|err| ParseError {
+        line: bus_line,
+        ..err
+    })?;
+    if let Some((extra_line, extra_text)) = lines.next() {
+        let leading = extra_text.len() - extra_text.trim_start().len();
+        return Err(ParseError {
+            line: extra_line,
+            column: leading + 1,
+            token: extra_text.trim().to_string(),
+            kind: ParseErrorKind::ExtraLine,
+        });
+    }
+    Ok((start_time, schedule))
+}
+
+/// The first time at or after `time` that a bus with the given ID departs.
+fn next_departure(bus: i64, time: i64) -> i64 {
+    time + (-time).rem_euclid(bus)
+}
+
+/// The smallest value at or after `time` that is congruent to `residue` modulo `modulus`.
+fn first_at_or_after(residue: i128, modulus: i128, time: i128) -> i128 {
+    time + (residue - time).rem_euclid(modulus)
+}
+
+/// The first time at or after `time` when every bus in `buses` departs at once.
+pub fn next_together(buses: &[i64], time: i64) -> Result<i128, CrtError> {
+    let offsets: Vec<(i64, i64)> = buses.iter().map(|&bus| (bus, 0)).collect();
+    next_with_offsets(&offsets, time)
+}
+
+/// The first time t at or after `time` when each bus in `offsets` departs at t plus
+/// its offset. The buses can be any IDs, not just ones from a schedule, and they
+/// don't have to be co-prime, but there's an error if no such time exists.
+pub fn next_with_offsets(offsets: &[(i64, i64)], time: i64) -> Result<i128, CrtError> {
+    let residues: Vec<i128> = offsets
+        .iter()
+        .map(|&(_, offset)| -(offset as i128))
+        .collect();
+    let moduli: Vec<i128> = offsets.iter().map(|&(bus, _)| bus as i128).collect();
+    let (residue, period) = chinese_remainder(&residues, &moduli)?;
+    Ok(first_at_or_after(residue, period, time as i128))
+}
+
+impl Schedule {
+    /// Parse a comma-separated bus list, where `x` marks a position with no bus.
+    /// Whitespace around entries (including a trailing CR) is ignored. Errors are
+    /// reported as if this were line 1 of a file.
+    pub fn parse(input: &str) -> Result<Schedule, ParseError> {
+        let mut slots = Vec::new();
+        for (column, token) in tokens(input) {
+            if token == "x" {
+                slots.push(None);
+            } else {
+                slots.push(Some(parse_at_least(1, token, 1, column)?));
+            }
+        }
+        Ok(Schedule { slots })
+    }
+
+    /// Each bus ID with its position in the list.
+    pub fn buses(&self) -> impl Iterator<Item = (usize, i64)> + '_ {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, slot)| slot.map(|bus| (index, bus)))
+    }
+
+    /// The bus that leaves soonest at or after `time`, and how long it is to wait for it,
+    /// or None if the schedule has no buses.
+    pub fn earliest_bus(&self, time: i64) -> Option<BusResult> {
+        self.buses()
+            .map(|(_, bus)| BusResult {
+                bus_number: bus,
+                wait_time: next_departure(bus, time) - time,
+            })
+            .min_by_key(|result| result.wait_time)
+    }
+
+    /// The next `k` departures at or after `time` of each bus, in the order of the list.
+    pub fn next_departures(&self, time: i64, k: usize) -> Vec<(i64, Vec<i64>)> {
+        self.buses()
+            .map(|(_, bus)| {
+                let first = next_departure(bus, time);
+                let times = (0..k as i64).map(|n| first + n * bus).collect();
+                (bus, times)
+            })
+            .collect()
+    }
+
+    /// The puzzle's part 2: the first time when each bus departs as many minutes later
+    /// as its position in the list.
+    pub fn solve_bus_puzzle(&self) -> Result<i128, CrtError> {
+        let offsets: Vec<(i64, i64)> = self
+            .buses()
+            .map(|(index, bus)| (bus, index as i64))
+            .collect();
+        next_with_offsets(&offsets, 0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn example() -> Schedule {
+        Schedule {
+            slots: vec![
+                Some(7),
+                Some(13),
+                None,
+                None,
+                Some(59),
+                None,
+                Some(31),
+                Some(19),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Schedule::parse("7,13,x,x,59,x,31,19"), Ok(example()));
+        assert_eq!(Schedule::parse(" 7, 13 ,x,x,59,x,31,19\r"), Ok(example()));
+        assert_eq!(
+            parse_input("939\r\n7,13,x,x,59,x,31,19\r\n\n"),
+            Ok((939, example()))
+        );
+
+        let err = Schedule::parse("7,13,y,0").unwrap_err();
+        assert_eq!((err.column, err.token.as_str()), (6, "y"));
+        assert_eq!(err.kind, ParseErrorKind::NotANumber);
+
+        let err = parse_input("939\n7,13, -4").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 7));
+        assert_eq!(err.kind, ParseErrorKind::NotPositive);
+
+        let err = parse_input("939\n7,,13").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 3));
+        assert_eq!(err.kind, ParseErrorKind::EmptyEntry);
+
+        let err = parse_input("  abc\n7,13").unwrap_err();
+        assert_eq!((err.line, err.column, err.token.as_str()), (1, 3, "abc"));
+        assert_eq!(err.kind, ParseErrorKind::NotATime);
+        assert_eq!(
+            err.to_string(),
+            "line 1, column 3: expected a departure time (found \"abc\")"
+        );
+
+        let err = parse_input("939\n").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::MissingLine);
+
+        let err = parse_input("939\n7,13\n\n  17,19\n").unwrap_err();
+        assert_eq!((err.line, err.column, err.token.as_str()), (4, 3, "17,19"));
+        assert_eq!(err.kind, ParseErrorKind::ExtraLine);
+    }
+
+    #[test]
+    fn test_queries() {
+        let schedule = example();
+        assert_eq!(
+            schedule.earliest_bus(939),
+            Some(BusResult {
+                bus_number: 59,
+                wait_time: 5
+            })
+        );
+        let no_buses = Schedule::parse("x,x").unwrap();
+        assert_eq!(no_buses.earliest_bus(939), None);
+        assert_eq!(schedule.solve_bus_puzzle(), Ok(1068781));
+
+        let departures = schedule.next_departures(939, 3);
+        assert_eq!(departures[0], (7, vec![945, 952, 959]));
+        assert_eq!(departures[2], (59, vec![944, 1003, 1062]));
+
+        assert_eq!(next_together(&[7, 13], 939), Ok(1001));
+        assert_eq!(next_together(&[4, 6], 13), Ok(24));
+        assert_eq!(next_with_offsets(&[(7, 0), (13, 1)], 0), Ok(77));
+        assert_eq!(next_with_offsets(&[(7, 0), (13, 1)], 78), Ok(168));
+        assert_eq!(
+            next_with_offsets(&[(4, 0), (6, 1)], 0),
+            Err(CrtError::Inconsistent { index: 1 })
+        );
+    }
+}
@@ -0,0 +1,180 @@
+/// A set of addresses written as a ternary pattern: each bit is either fixed to 0 or 1,
+/// or floating, in which case it can be either. A pattern with n floating bits stands
+/// for 2^n addresses.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct AddressPattern {
+    pub fixed: u64,    // the values of the fixed bits, with 0s where the bits float
+    pub floating: u64, // a binary number with 1s where the bits float, 0s otherwise
+}
+
+impl AddressPattern {
+    pub fn new(fixed: u64, floating: u64) -> Self {
+        AddressPattern {
+            fixed: fixed & !floating,
+            floating,
+        }
+    }
+
+    /// How many addresses the pattern matches.
+    pub fn size(&self) -> u128 {
+        1 << self.floating.count_ones()
+    }
+
+    pub fn contains(&self, addr: u64) -> bool {
+        addr & !self.floating == self.fixed
+    }
+
+    /// Whether any address matches both patterns. They only miss each other if they
+    /// have a bit that's fixed in both, to different values.
+    pub fn overlaps(&self, other: &AddressPattern) -> bool {
+        let both_fixed = !self.floating & !other.floating;
+        (self.fixed ^ other.fixed) & both_fixed == 0
+    }
+
+    /// The addresses matched by this pattern but not by `other`, as a list of patterns
+    /// that don't overlap each other. There's at most one of them for each bit that
+    /// floats here but is fixed in `other`.
+    pub fn subtract(&self, other: &AddressPattern) -> Vec<AddressPattern> {
+        if !self.overlaps(other) {
+            return vec![*self];
+        }
+        let mut pieces = Vec::new();
+        let mut remaining = *self;
+        let mut splits = self.floating & !other.floating;
+        while splits != 0 {
+            let bit = splits & splits.wrapping_neg();
+            splits &= !bit;
+            // The piece where this bit differs from `other` is outside it; carry on
+            // splitting the piece where it's the same.
+            remaining.floating &= !bit;
+            pieces.push(AddressPattern {
+                fixed: remaining.fixed | (!other.fixed & bit),
+                floating: remaining.floating,
+            });
+            remaining.fixed |= other.fixed & bit;
+        }
+        pieces
+    }
+
+    /// The pattern as a string of 0, 1 and X, like the masks in a program.
+    pub fn to_ternary(self, width: u32) -> String {
+        (0..width)
+            .rev()
+            .map(|bit| {
+                if self.floating >> bit & 1 == 1 {
+                    'X'
+                } else if self.fixed >> bit & 1 == 1 {
+                    '1'
+                } else {
+                    '0'
+                }
+            })
+            .collect()
+    }
+
+    /// Every address the pattern matches, in increasing order. The decoder never
+    /// needs this, but it's how the tests check patterns against the old expansion.
+    #[allow(dead_code)]
+    pub fn addresses(&self) -> impl Iterator<Item = u64> {
+        let AddressPattern { fixed, floating } = *self;
+        // Step through the subsets of the floating bits by counting in their positions
+        let mut next = Some(0u64);
+        std::iter::from_fn(move || {
+            let subset = next?;
+            next = match subset.wrapping_sub(floating) & floating {
+                0 => None,
+                following => Some(following),
+            };
+            Some(fixed | subset)
+        })
+    }
+}
+
+/// Memory for the version 2 decoder, which keeps each write as a pattern instead of
+/// writing every address separately. When a write overlaps earlier ones, the overlap
+/// is cut out of the earlier patterns, so no address is ever stored twice.
+#[derive(Clone, Debug, Default)]
+pub struct FloatingMemory {
+    writes: Vec<(AddressPattern, u64)>,
+}
+
+impl FloatingMemory {
+    pub fn new() -> Self {
+        FloatingMemory::default()
+    }
+
+    pub fn write(&mut self, pattern: AddressPattern, value: u64) {
+        let mut writes = Vec::with_capacity(self.writes.len() + 1);
+        for (old, old_value) in self.writes.drain(..) {
+            writes.extend(old.subtract(&pattern).into_iter().map(|p| (p, old_value)));
+        }
+        writes.push((pattern, value));
+        self.writes = writes;
+    }
+
+    pub fn read(&self, addr: u64) -> Option<u64> {
+        self.writes
+            .iter()
+            .find(|(pattern, _)| pattern.contains(addr))
+            .map(|&(_, value)| value)
+    }
+
+    /// How many addresses have been written to.
+    pub fn len(&self) -> u128 {
+        self.writes.iter().map(|(pattern, _)| pattern.size()).sum()
+    }
+
+    /// The sum of the values at every address. This can be bigger than a u64 when a
+    /// write has a lot of floating bits.
+    pub fn total(&self) -> u128 {
+        self.writes
+            .iter()
+            .map(|&(pattern, value)| pattern.size() * value as u128)
+            .sum()
+    }
+
+    /// The patterns currently holding values, each with its value.
+    pub fn patterns(&self) -> &[(AddressPattern, u64)] {
+        &self.writes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_subtract() {
+        // 0X1X minus XX11 leaves 0X10
+        let a = AddressPattern::new(0b0010, 0b0101);
+        let b = AddressPattern::new(0b0011, 0b1100);
+        let pieces = a.subtract(&b);
+        let mut left: Vec<u64> = pieces.iter().flat_map(|p| p.addresses()).collect();
+        left.sort();
+        assert_eq!(left, vec![0b0010, 0b0110]);
+
+        let c = AddressPattern::new(0b1000, 0b0011);
+        assert!(!a.overlaps(&c));
+        assert_eq!(a.subtract(&c), vec![a]);
+        assert_eq!(a.subtract(&a), vec![]);
+    }
+
+    #[test]
+    fn test_memory() {
+        let mut memory = FloatingMemory::new();
+        memory.write(AddressPattern::new(0b011010, 0b100001), 100);
+        memory.write(AddressPattern::new(0b010000, 0b001011), 1);
+        assert_eq!(memory.total(), 208);
+        assert_eq!(memory.len(), 10);
+        assert_eq!(memory.read(0b011010), Some(1));
+        assert_eq!(memory.read(0b111011), Some(100));
+        assert_eq!(memory.read(0), None);
+
+        // Forty floating bits would be a trillion separate writes
+        let mut memory = FloatingMemory::new();
+        memory.write(AddressPattern::new(0, (1 << 40) - 1), 3);
+        memory.write(AddressPattern::new(1, (1 << 40) - 2), 5);
+        assert_eq!(memory.len(), 1 << 40);
+        assert_eq!(memory.total(), 4 << 40);
+    }
+}
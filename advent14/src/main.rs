@@ -1,21 +1,34 @@
-use nom::bytes::complete::{tag, take_while_m_n};
-use nom::character::complete::digit1;
+mod floating;
+mod trace;
+
+use floating::{AddressPattern, FloatingMemory};
+use nom::bytes::complete::{tag, take_while1};
+use nom::character::complete::{digit1, space0};
 use nom::combinator::map_res;
 use nom::IResult;
-use nom::{branch::alt, multi::separated_list1};
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use trace::{diff_memories, MemoryDump, WriteRecord};
+
+/// The word width of the original docking computer, used when a program has no masks
+/// to infer the width from.
+const DEFAULT_WIDTH: u32 = 36;
+
+/// The largest word width we can emulate.
+const MAX_WIDTH: u32 = 64;
 
 #[derive(Copy, Clone, PartialEq, Debug)]
 struct Bitmask {
     zeros: u64, // a binary number with 0s where the zeros are, 1s otherwise
     ones: u64,  // a binary number with 1s where the ones are, 0s otherwise
+    width: u32, // how many bits the mask covers
 }
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Copy, Clone, PartialEq, Debug)]
 struct FloatingMask {
-    ones: u64, // a binary number with 1s where the ones are, 0s otherwise
-    zeros: u64,
-    x_locations: Vec<usize>,
+    ones: u64,     // a binary number with 1s where the ones are, 0s otherwise
+    floating: u64, // a binary number with 1s where the Xs are, 0s otherwise
 }
 
 #[derive(Copy, Clone, PartialEq, Debug)]
@@ -25,62 +38,199 @@ enum Instruction {
 }
 use self::Instruction::*;
 
-fn apply_bitmask(mask: Bitmask, value: u64) -> u64 {
-    assert!(value < (1 << 36));
-    (value | mask.ones) & mask.zeros
+/// A docking program, along with the width of the words it works on.
+#[derive(Clone, PartialEq, Debug)]
+struct Program {
+    width: u32,
+    instructions: Vec<Instruction>,
 }
 
-fn bitmask_to_floating(mask: Bitmask) -> FloatingMask {
-    let mut locations: Vec<usize> = Vec::new();
-    for bit in 0usize..36 {
-        let val: u64 = 1 << bit;
-        if val & mask.zeros & (!(mask.ones)) > 0 {
-            locations.push(bit);
+#[derive(Clone, PartialEq, Debug)]
+enum ProgramError {
+    /// A mask had a different width from the first mask in the program.
+    MaskWidthMismatch {
+        index: usize,
+        expected: u32,
+        found: u32,
+    },
+    /// A value didn't fit in the program's word width.
+    ValueOverflow {
+        index: usize,
+        value: u64,
+        width: u32,
+    },
+    /// An address didn't fit in the program's word width.
+    AddressOverflow {
+        index: usize,
+        addr: usize,
+        width: u32,
+    },
+}
+
+impl fmt::Display for ProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProgramError::MaskWidthMismatch {
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "instruction {}: mask is {} bits wide, but the program uses {} bits",
+                index, found, expected
+            ),
+            ProgramError::ValueOverflow {
+                index,
+                value,
+                width,
+            } => write!(
+                f,
+                "instruction {}: value {} doesn't fit in {} bits",
+                index, value, width
+            ),
+            ProgramError::AddressOverflow { index, addr, width } => write!(
+                f,
+                "instruction {}: address {} doesn't fit in {} bits",
+                index, addr, width
+            ),
         }
     }
-    let mut new_zeros: u64 = 0;
-    for bit in locations.clone() {
-        new_zeros |= 1 << bit
-    }
-    FloatingMask {
-        ones: mask.ones,
-        zeros: !new_zeros,
-        x_locations: locations,
+}
+
+impl std::error::Error for ProgramError {}
+
+/// A number with 1s in the lowest `width` bits.
+fn word_mask(width: u32) -> u64 {
+    if width >= 64 {
+        u64::MAX
+    } else {
+        (1 << width) - 1
     }
 }
 
-fn apply_floating_mask(mask: &FloatingMask, addr: u64) -> HashSet<u64> {
-    let n = mask.x_locations.len();
-    let mut modified_addrs: HashSet<u64> = HashSet::new();
-    for step in 0..(1 << n) {
-        let mut bit_pattern: u64 = 0;
-        for bit in 0..n {
-            // if there's a 1 in the correct place in the step number
-            if step & (1 << bit) != 0 {
-                // set a 1 in the corresponding place in the bit pattern
-                bit_pattern |= 1 << mask.x_locations[bit];
+impl Program {
+    /// Make a program out of a list of instructions, taking the word width from its
+    /// masks, which all have to be the same width.
+    fn new(instructions: Vec<Instruction>) -> Result<Program, ProgramError> {
+        let mut width: Option<u32> = None;
+        for (index, inst) in instructions.iter().enumerate() {
+            if let SetMask(mask) = inst {
+                match width {
+                    None => width = Some(mask.width),
+                    Some(expected) if expected != mask.width => {
+                        return Err(ProgramError::MaskWidthMismatch {
+                            index,
+                            expected,
+                            found: mask.width,
+                        });
+                    }
+                    _ => {}
+                }
             }
         }
-        let modified_addr = ((addr | mask.ones) & mask.zeros) + bit_pattern;
-        modified_addrs.insert(modified_addr);
+        Ok(Program {
+            width: width.unwrap_or(DEFAULT_WIDTH),
+            instructions,
+        })
+    }
+
+    /// The mask that's in effect before the program sets one, which leaves values
+    /// unchanged.
+    fn initial_mask(&self) -> Bitmask {
+        Bitmask {
+            zeros: word_mask(self.width),
+            ones: 0,
+            width: self.width,
+        }
+    }
+
+    /// Check that the address and value of a SetValue instruction fit in a word.
+    fn check_fits(&self, index: usize, addr: usize, value: u64) -> Result<(), ProgramError> {
+        let limit = word_mask(self.width);
+        if value & !limit != 0 {
+            Err(ProgramError::ValueOverflow {
+                index,
+                value,
+                width: self.width,
+            })
+        } else if addr as u64 & !limit != 0 {
+            Err(ProgramError::AddressOverflow {
+                index,
+                addr,
+                width: self.width,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn apply_bitmask(mask: Bitmask, value: u64) -> u64 {
+    (value | mask.ones) & mask.zeros
+}
+
+fn bitmask_to_floating(mask: Bitmask) -> FloatingMask {
+    FloatingMask {
+        ones: mask.ones,
+        floating: mask.zeros & !mask.ones & word_mask(mask.width),
     }
-    modified_addrs
+}
+
+/// The addresses that a version 2 decoder writes to, as a pattern rather than a list,
+/// since there are 2^n of them for a mask with n Xs.
+fn apply_floating_mask(mask: FloatingMask, addr: u64) -> AddressPattern {
+    AddressPattern::new(addr | mask.ones, mask.floating)
 }
 
 fn is_mask_character(c: char) -> bool {
     c == '0' || c == '1' || c == 'X'
 }
 
-fn parse_mask(input: &str) -> IResult<&str, Instruction> {
-    let (input, _) = tag("mask = ")(input)?;
-    let (input, mask_chars) = take_while_m_n(36, 36, is_mask_character)(input)?;
-    let ones_chars = mask_chars.replace("X", "0");
-    let zeros_chars = mask_chars.replace("X", "1");
+/// A parser failure: what the parser was looking for, and the input where it was
+/// looking for it.
+#[derive(Debug, PartialEq)]
+struct Expected<'a> {
+    input: &'a str,
+    expected: &'static str,
+}
+
+type ParseResult<'a, O> = IResult<&'a str, O, Expected<'a>>;
+
+/// Run a nom parser, describing what it was looking for if it fails.
+fn expect<'a, O>(
+    expected: &'static str,
+    mut parser: impl FnMut(&'a str) -> IResult<&'a str, O>,
+) -> impl FnMut(&'a str) -> ParseResult<'a, O> {
+    move |input| {
+        parser(input).map_err(|err| {
+            err.map(|err: nom::error::Error<&str>| Expected {
+                input: err.input,
+                expected,
+            })
+        })
+    }
+}
+
+fn fail<'a, O>(input: &'a str, expected: &'static str) -> ParseResult<'a, O> {
+    Err(nom::Err::Error(Expected { input, expected }))
+}
+
+fn parse_mask(input: &str) -> ParseResult<'_, Instruction> {
+    let (input, _) = expect("`mask =`", tag("mask ="))(input)?;
+    let (input, _) = space0::<_, ()>(input).unwrap();
+    let (rest, mask_chars) =
+        expect("a mask made of 0, 1 and X", take_while1(is_mask_character))(input)?;
+    if mask_chars.len() > MAX_WIDTH as usize {
+        return fail(input, "a mask of at most 64 bits");
+    }
+    let ones_chars = mask_chars.replace('X', "0");
+    let zeros_chars = mask_chars.replace('X', "1");
     let mask = Bitmask {
         ones: u64::from_str_radix(&ones_chars, 2).unwrap(),
         zeros: u64::from_str_radix(&zeros_chars, 2).unwrap(),
+        width: mask_chars.len() as u32,
     };
-    Ok((input, SetMask(mask)))
+    Ok((rest, SetMask(mask)))
 }
 
 /// Parse a decimal integer (with no sign) and return it as a u64.
@@ -89,74 +239,217 @@ fn parse_u64(input: &str) -> IResult<&str, u64> {
     map_res(digit1, str::parse)(input)
 }
 
-fn parse_set_value(input: &str) -> IResult<&str, Instruction> {
-    let (input, _) = tag("mem[")(input)?;
-    let (input, addr) = parse_u64(input)?;
-    let (input, _) = tag("] = ")(input)?;
-    let (input, value) = parse_u64(input)?;
+fn parse_set_value(input: &str) -> ParseResult<'_, Instruction> {
+    let (input, _) = expect("`mem[`", tag("mem["))(input)?;
+    let (input, addr) = expect("an address", parse_u64)(input)?;
+    let (input, _) = expect("`] =`", tag("] ="))(input)?;
+    let (input, _) = space0::<_, ()>(input).unwrap();
+    let (input, value) = expect("a 64-bit value", parse_u64)(input)?;
     Ok((input, SetValue(addr as usize, value)))
 }
 
-fn parse_instruction_list(input: &str) -> IResult<&str, Vec<Instruction>> {
-    separated_list1(tag("\n"), alt((parse_set_value, parse_mask)))(input)
+/// Parse the instruction on one line, which has to take up the whole line.
+fn parse_line(input: &str) -> ParseResult<'_, Instruction> {
+    let (input, inst) = if input.starts_with("mask") {
+        parse_mask(input)?
+    } else if input.starts_with("mem") {
+        parse_set_value(input)?
+    } else {
+        return fail(input, "`mask` or `mem`");
+    };
+    if !input.trim().is_empty() {
+        return fail(input.trim_start(), "the end of the line");
+    }
+    Ok((input, inst))
+}
+
+/// A problem with a program listing, pointing to the line and column (both starting
+/// at 1) where the parser found something other than what it expected.
+#[derive(Debug, Clone, PartialEq)]
+struct ParseError {
+    line: usize,
+    column: usize,
+    expected: &'static str,
+    found: String,
 }
 
-// Parse a listing of instructions, and panic if it doesn't work.
-fn parse_instructions(input: &str) -> Vec<Instruction> {
-    let (input, instructions) = parse_instruction_list(input).unwrap();
-    assert_eq!(input.trim(), "");
-    instructions
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: expected {}, found ",
+            self.line, self.column, self.expected
+        )?;
+        if self.found.is_empty() {
+            write!(f, "the end of the line")
+        } else {
+            write!(f, "{:?}", self.found)
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parse a listing of instructions, one per line. Anything after a `#` is a comment,
+/// and blank lines are skipped. Lines can end in either LF or CRLF.
+fn parse_instructions(input: &str) -> Result<Vec<Instruction>, ParseError> {
+    let mut instructions = Vec::new();
+    for (index, line) in input.lines().enumerate() {
+        let code = match line.find('#') {
+            Some(pos) => &line[..pos],
+            None => line,
+        };
+        let code = code.trim_end();
+        let start = code.trim_start();
+        if start.is_empty() {
+            continue;
+        }
+        match parse_line(start) {
+            Ok((_, inst)) => instructions.push(inst),
+            Err(nom::Err::Error(err)) | Err(nom::Err::Failure(err)) => {
+                let offset = code.len() - err.input.len();
+                return Err(ParseError {
+                    line: index + 1,
+                    column: code[..offset].chars().count() + 1,
+                    expected: err.expected,
+                    found: err
+                        .input
+                        .split_whitespace()
+                        .next()
+                        .unwrap_or("")
+                        .to_string(),
+                });
+            }
+            Err(nom::Err::Incomplete(_)) => unreachable!("complete parsers don't ask for more"),
+        }
+    }
+    Ok(instructions)
 }
 
-fn run_instructions_step1(instructions: &[Instruction]) -> HashMap<usize, u64> {
+/// Run a program with the version 1 decoder, calling `on_write` with each write it
+/// makes.
+fn run_instructions_step1(
+    program: &Program,
+    mut on_write: impl FnMut(&WriteRecord),
+) -> Result<HashMap<usize, u64>, ProgramError> {
     let mut memory: HashMap<usize, u64> = HashMap::new();
-    let mut current_mask = Bitmask {
-        zeros: 1 << 36 - 1,
-        ones: 0,
-    };
+    let mut current_mask = program.initial_mask();
 
-    for inst in instructions {
+    for (index, inst) in program.instructions.iter().enumerate() {
         match inst {
             SetMask(mask) => current_mask = *mask,
             SetValue(addr, value) => {
-                memory.insert(*addr, apply_bitmask(current_mask, *value));
-                ()
+                program.check_fits(index, *addr, *value)?;
+                let masked = apply_bitmask(current_mask, *value);
+                on_write(&WriteRecord {
+                    index,
+                    address: AddressPattern::new(*addr as u64, 0),
+                    value_before: *value,
+                    value_after: masked,
+                });
+                memory.insert(*addr, masked);
             }
         }
     }
-    memory
+    Ok(memory)
 }
 
-fn run_instructions_step2(instructions: &[Instruction]) -> HashMap<usize, u64> {
-    let mut memory: HashMap<usize, u64> = HashMap::new();
-    let mut current_mask = bitmask_to_floating(Bitmask {
-        zeros: 1 << 36 - 1,
-        ones: 0,
-    });
+/// Run a program with the version 2 decoder, calling `on_write` with each write it
+/// makes.
+fn run_instructions_step2(
+    program: &Program,
+    mut on_write: impl FnMut(&WriteRecord),
+) -> Result<FloatingMemory, ProgramError> {
+    let mut memory = FloatingMemory::new();
+    let mut current_mask = bitmask_to_floating(program.initial_mask());
 
-    for inst in instructions {
+    for (index, inst) in program.instructions.iter().enumerate() {
         match inst {
             SetMask(mask) => current_mask = bitmask_to_floating(*mask),
             SetValue(addr, value) => {
-                for new_addr in apply_floating_mask(&current_mask, *addr as u64) {
-                    memory.insert(new_addr as usize, *value);
+                program.check_fits(index, *addr, *value)?;
+                let pattern = apply_floating_mask(current_mask, *addr as u64);
+                on_write(&WriteRecord {
+                    index,
+                    address: pattern,
+                    value_before: *value,
+                    value_after: *value,
+                });
+                memory.write(pattern, *value);
+            }
+        }
+    }
+    Ok(memory)
+}
+
+/// What to print besides the answers, chosen on the command line.
+#[derive(Default)]
+struct Output {
+    trace: bool,
+    dump_format: Option<String>,
+    diff: bool,
+}
+
+impl Output {
+    fn from_args(args: &[String]) -> Result<Self, String> {
+        let mut output = Output::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--trace" => output.trace = true,
+                "--dump" => {
+                    let format = args.next().ok_or("--dump needs a format: text or json")?;
+                    if format != "text" && format != "json" {
+                        return Err(format!("unknown dump format: {}", format));
+                    }
+                    output.dump_format = Some(format.clone());
                 }
+                "--diff" => output.diff = true,
+                other => return Err(format!("unknown option: {}", other)),
             }
         }
+        Ok(output)
+    }
+
+    fn print_dump(&self, dump: &MemoryDump) {
+        match self.dump_format.as_deref() {
+            Some("json") => print!("{}", dump.to_json()),
+            Some(_) => print!("{}", dump.to_text()),
+            None => {}
+        }
     }
-    memory
 }
 
-fn main() {
-    let input = std::fs::read_to_string("input.txt").unwrap();
-    let instructions = parse_instructions(&input);
-    let memory = run_instructions_step1(&instructions);
-    let answer: u64 = memory.values().sum();
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // `--trace` prints every write, `--dump text|json` prints the final memory of each
+    // decoder, and `--diff` shows where the two decoders disagree
+    let args: Vec<String> = env::args().skip(1).collect();
+    let output = Output::from_args(&args)?;
+    let input = std::fs::read_to_string("input.txt")?;
+    let program = Program::new(parse_instructions(&input)?)?;
+    let print_write = |write: &WriteRecord| {
+        if output.trace {
+            println!("{}", write.to_text(program.width));
+        }
+    };
+
+    let memory1 = run_instructions_step1(&program, print_write)?;
+    output.print_dump(&MemoryDump::from_v1(&memory1, program.width));
+    let answer: u64 = memory1.values().sum();
     println!("Total of memory values in version 1: {}", answer);
 
-    let memory = run_instructions_step2(&instructions);
-    let answer: u64 = memory.values().sum();
+    let memory2 = run_instructions_step2(&program, print_write)?;
+    output.print_dump(&MemoryDump::from_v2(&memory2, program.width));
+    let answer = memory2.total();
     println!("Total of memory values in version 2: {}", answer);
+    println!("Addresses written in version 2: {}", memory2.len());
+
+    if output.diff {
+        for entry in diff_memories(&memory1, &memory2) {
+            println!("{}", entry.to_text(program.width));
+        }
+    }
+    Ok(())
 }
 
 #[test]
@@ -165,6 +458,7 @@ fn test_bitmask() {
     let mask: Bitmask = Bitmask {
         zeros: 0b111111111111111111111111111111111101,
         ones: 0b000000000000000000000000000001000000,
+        width: 36,
     };
     assert_eq!(apply_bitmask(mask, 0b1011), 0b1001001);
     assert_eq!(apply_bitmask(mask, 101), 101);
@@ -177,11 +471,12 @@ fn test_parse() {
 mem[8] = 11
 mem[7] = 101
 mem[8] = 0";
-    let parsed = parse_instructions(example);
+    let parsed = parse_instructions(example).unwrap();
     let expected = vec![
         SetMask(Bitmask {
             zeros: 0b111111111111111111111111111111111101,
             ones: 0b000000000000000000000000000001000000,
+            width: 36,
         }),
         SetValue(8, 11),
         SetValue(7, 101),
@@ -195,12 +490,159 @@ fn test_floating_mask() {
     let mask = Bitmask {
         zeros: 0b000000000000000000000000000000110011,
         ones: 0b000000000000000000000000000000010010,
+        width: 36,
     };
     let floating_mask = bitmask_to_floating(mask);
     println!("mask is: {:?}", floating_mask);
-    let mut assigned_addresses: Vec<u64> = apply_floating_mask(&floating_mask, 42)
-        .into_iter()
-        .collect();
-    assigned_addresses.sort();
+    let assigned_addresses: Vec<u64> = apply_floating_mask(floating_mask, 42).addresses().collect();
     assert_eq!(assigned_addresses, vec![26, 27, 58, 59]);
 }
+
+#[test]
+fn test_word_width() {
+    let example = "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X
+mem[8] = 11
+mem[7] = 101
+mem[8] = 0";
+    let program = Program::new(parse_instructions(example).unwrap()).unwrap();
+    assert_eq!(program.width, 36);
+    let memory = run_instructions_step1(&program, |_| {}).unwrap();
+    assert_eq!(memory.values().sum::<u64>(), 165);
+
+    // A 48-bit program can store values that don't fit in 36 bits
+    let wide = format!("mask = {}X1\nmem[3] = {}", "X".repeat(46), 1u64 << 40);
+    let program = Program::new(parse_instructions(&wide).unwrap()).unwrap();
+    assert_eq!(program.width, 48);
+    let memory = run_instructions_step1(&program, |_| {}).unwrap();
+    assert_eq!(memory[&3], (1 << 40) + 1);
+
+    let too_big = "mask = XXXX\nmem[3] = 16";
+    let program = Program::new(parse_instructions(too_big).unwrap()).unwrap();
+    assert_eq!(
+        run_instructions_step1(&program, |_| {}),
+        Err(ProgramError::ValueOverflow {
+            index: 1,
+            value: 16,
+            width: 4
+        })
+    );
+
+    let mismatched = "mask = XXXX\nmask = XXX";
+    assert_eq!(
+        Program::new(parse_instructions(mismatched).unwrap()),
+        Err(ProgramError::MaskWidthMismatch {
+            index: 1,
+            expected: 4,
+            found: 3
+        })
+    );
+}
+
+#[test]
+fn test_floating_memory() {
+    let example = "mask = 000000000000000000000000000000X1001X
+mem[42] = 100
+mask = 00000000000000000000000000000000X0XX
+mem[26] = 1";
+    let program = Program::new(parse_instructions(example).unwrap()).unwrap();
+    let memory = run_instructions_step2(&program, |_| {}).unwrap();
+    assert_eq!(memory.total(), 208);
+    assert_eq!(memory.len(), 10);
+    assert_eq!(memory.read(58), Some(100));
+    assert_eq!(memory.read(16), Some(1));
+}
+
+#[test]
+fn test_parse_errors() {
+    let listing = "# set up the mask\r\nmask = X1X0\r\n\r\nmem[8] = 11  # overwritten later\r\n";
+    assert_eq!(
+        parse_instructions(listing),
+        Ok(vec![
+            SetMask(Bitmask {
+                zeros: 0b1110,
+                ones: 0b0100,
+                width: 4,
+            }),
+            SetValue(8, 11),
+        ])
+    );
+
+    fn error(input: &str) -> ParseError {
+        parse_instructions(input).unwrap_err()
+    }
+    assert_eq!(
+        error("mask = X1X0\nmem[8] = 11\n  mem[x] = 3"),
+        ParseError {
+            line: 3,
+            column: 7,
+            expected: "an address",
+            found: "x]".to_string(),
+        }
+    );
+    assert_eq!(error("mask = X1X2").column, 11);
+    assert_eq!(error("mask = X1X2").expected, "the end of the line");
+    assert_eq!(error("mem[8] = ").expected, "a 64-bit value");
+    assert_eq!(error("mem[8] = 99999999999999999999").column, 10);
+    assert_eq!(error("mems[8] = 1").expected, "`mem[`");
+    assert_eq!(error("\nmov 1 2").expected, "`mask` or `mem`");
+    assert_eq!(error("\nmov 1 2").line, 2);
+    assert_eq!(
+        error(&format!("mask = {}", "X".repeat(65))).expected,
+        "a mask of at most 64 bits"
+    );
+    assert_eq!(
+        error("mem[8) = 11").to_string(),
+        "line 1, column 6: expected `] =`, found \")\""
+    );
+}
+
+#[test]
+fn test_trace() {
+    let example = "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X
+mem[8] = 11
+mem[7] = 101
+mem[8] = 0";
+    let program = Program::new(parse_instructions(example).unwrap()).unwrap();
+    let mut writes = Vec::new();
+    run_instructions_step1(&program, |write| writes.push(*write)).unwrap();
+    assert_eq!(writes.len(), 3);
+    assert_eq!(
+        writes[0],
+        WriteRecord {
+            index: 1,
+            address: AddressPattern::new(8, 0),
+            value_before: 11,
+            value_after: 73,
+        }
+    );
+    assert_eq!(writes[2].to_text(program.width), "3: mem[8] = 0 -> 64");
+
+    let mut lines = Vec::new();
+    run_instructions_step2(&program, |write| lines.push(write.to_text(program.width))).unwrap();
+    assert_eq!(
+        lines[1],
+        "2: mem[XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX1X] = 101 -> 101"
+    );
+}
+
+#[test]
+fn test_output_args() {
+    let args: Vec<String> = ["--trace", "--dump", "json"]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+    let output = Output::from_args(&args).unwrap();
+    assert!(output.trace);
+    assert_eq!(output.dump_format.as_deref(), Some("json"));
+
+    let bad = |args: &[&str]| {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        Output::from_args(&args).err()
+    };
+    assert_eq!(
+        bad(&["--dump"]).unwrap(),
+        "--dump needs a format: text or json"
+    );
+    assert_eq!(bad(&["--dump", "xml"]).unwrap(), "unknown dump format: xml");
+    assert_eq!(bad(&["--verbose"]).unwrap(), "unknown option: --verbose");
+}
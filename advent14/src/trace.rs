@@ -0,0 +1,207 @@
+use crate::floating::{AddressPattern, FloatingMemory};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// One write to memory made while running a program. The version 1 decoder writes a
+/// single address and masks the value; the version 2 decoder writes a whole pattern
+/// of addresses and stores the value as it is.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct WriteRecord {
+    pub index: usize,
+    pub address: AddressPattern,
+    pub value_before: u64,
+    pub value_after: u64,
+}
+
+/// An address as a plain number if it's a single address, or as a pattern of 0, 1 and
+/// X if it floats.
+fn format_address(address: &AddressPattern, width: u32) -> String {
+    if address.floating == 0 {
+        address.fixed.to_string()
+    } else {
+        address.to_ternary(width)
+    }
+}
+
+impl WriteRecord {
+    pub fn to_text(self, width: u32) -> String {
+        format!(
+            "{}: mem[{}] = {} -> {}",
+            self.index,
+            format_address(&self.address, width),
+            self.value_before,
+            self.value_after
+        )
+    }
+}
+
+/// The contents of memory at the end of a program, sorted by address. Each entry is a
+/// pattern of addresses that all hold the same value.
+#[derive(Clone, PartialEq, Debug)]
+pub struct MemoryDump {
+    pub width: u32,
+    pub entries: Vec<(AddressPattern, u64)>,
+}
+
+impl MemoryDump {
+    pub fn new(width: u32, mut entries: Vec<(AddressPattern, u64)>) -> Self {
+        entries.sort_by_key(|(address, _)| (address.fixed, address.floating));
+        MemoryDump { width, entries }
+    }
+
+    pub fn from_v1(memory: &HashMap<usize, u64>, width: u32) -> Self {
+        let entries = memory
+            .iter()
+            .map(|(&addr, &value)| (AddressPattern::new(addr as u64, 0), value))
+            .collect();
+        MemoryDump::new(width, entries)
+    }
+
+    pub fn from_v2(memory: &FloatingMemory, width: u32) -> Self {
+        MemoryDump::new(width, memory.patterns().to_vec())
+    }
+
+    /// One line per entry, in the same syntax the program would use to write it.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for (address, value) in &self.entries {
+            writeln!(
+                text,
+                "mem[{}] = {}",
+                format_address(address, self.width),
+                value
+            )
+            .unwrap();
+        }
+        text
+    }
+
+    /// A JSON list of entries. Single addresses are given as numbers under "address";
+    /// floating ones are given under "pattern", along with how many addresses they
+    /// cover.
+    pub fn to_json(&self) -> String {
+        let entries: Vec<String> = self
+            .entries
+            .iter()
+            .map(|(address, value)| {
+                if address.floating == 0 {
+                    format!("{{\"address\":{},\"value\":{}}}", address.fixed, value)
+                } else {
+                    format!(
+                        "{{\"pattern\":\"{}\",\"count\":{},\"value\":{}}}",
+                        address.to_ternary(self.width),
+                        address.size(),
+                        value
+                    )
+                }
+            })
+            .collect();
+        format!("[{}]\n", entries.join(","))
+    }
+}
+
+/// A place where the two decoders left memory different. A value of None means that
+/// decoder never wrote there.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct DiffEntry {
+    pub address: AddressPattern,
+    pub v1: Option<u64>,
+    pub v2: Option<u64>,
+}
+
+impl DiffEntry {
+    pub fn to_text(self, width: u32) -> String {
+        let show = |value: Option<u64>| value.map_or("-".to_string(), |v| v.to_string());
+        format!(
+            "mem[{}]: v1 = {}, v2 = {}",
+            format_address(&self.address, width),
+            show(self.v1),
+            show(self.v2)
+        )
+    }
+}
+
+/// Compare the memory left by the two decoders, sorted by address. Addresses that
+/// only the version 2 decoder wrote are kept as patterns, so this stays small even
+/// when it wrote a lot of them.
+pub fn diff_memories(v1: &HashMap<usize, u64>, v2: &FloatingMemory) -> Vec<DiffEntry> {
+    let mut diff = Vec::new();
+    for (&addr, &value) in v1 {
+        let other = v2.read(addr as u64);
+        if other != Some(value) {
+            diff.push(DiffEntry {
+                address: AddressPattern::new(addr as u64, 0),
+                v1: Some(value),
+                v2: other,
+            });
+        }
+    }
+    for &(pattern, value) in v2.patterns() {
+        let mut pieces = vec![pattern];
+        for &addr in v1.keys().filter(|&&addr| pattern.contains(addr as u64)) {
+            let single = AddressPattern::new(addr as u64, 0);
+            pieces = pieces
+                .iter()
+                .flat_map(|piece| piece.subtract(&single))
+                .collect();
+        }
+        diff.extend(pieces.into_iter().map(|address| DiffEntry {
+            address,
+            v1: None,
+            v2: Some(value),
+        }));
+    }
+    diff.sort_by_key(|entry| (entry.address.fixed, entry.address.floating));
+    diff
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_dump() {
+        let mut v1 = HashMap::new();
+        v1.insert(8, 64);
+        v1.insert(7, 101);
+        let dump = MemoryDump::from_v1(&v1, 6);
+        assert_eq!(dump.to_text(), "mem[7] = 101\nmem[8] = 64\n");
+        assert_eq!(
+            dump.to_json(),
+            "[{\"address\":7,\"value\":101},{\"address\":8,\"value\":64}]\n"
+        );
+
+        let mut v2 = FloatingMemory::new();
+        v2.write(AddressPattern::new(0b011010, 0b100001), 100);
+        v2.write(AddressPattern::new(0b010000, 0b001011), 1);
+        let dump = MemoryDump::from_v2(&v2, 6);
+        assert_eq!(dump.to_text(), "mem[01X0XX] = 1\nmem[11101X] = 100\n");
+        assert!(dump
+            .to_json()
+            .starts_with("[{\"pattern\":\"01X0XX\",\"count\":8,\"value\":1},"));
+    }
+
+    #[test]
+    fn test_diff() {
+        let mut v1 = HashMap::new();
+        v1.insert(1, 5);
+        v1.insert(2, 7);
+        v1.insert(9, 3);
+        let mut v2 = FloatingMemory::new();
+        v2.write(AddressPattern::new(0, 0b11), 5);
+
+        let diff: Vec<String> = diff_memories(&v1, &v2)
+            .iter()
+            .map(|entry| entry.to_text(4))
+            .collect();
+        assert_eq!(
+            diff,
+            vec![
+                "mem[0]: v1 = -, v2 = 5",
+                "mem[2]: v1 = 7, v2 = 5",
+                "mem[3]: v1 = -, v2 = 5",
+                "mem[9]: v1 = 3, v2 = -",
+            ]
+        );
+    }
+}
@@ -0,0 +1,22 @@
+use advent15::{elf_sequence, elf_sequence_hashmap};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+fn compare_engines(c: &mut Criterion) {
+    let init = [16, 11, 15, 0, 1, 7];
+    let mut group = c.benchmark_group("memory game");
+    group.sample_size(10);
+    for &steps in &[2020, 300_000, 3_000_000] {
+        group.bench_with_input(
+            BenchmarkId::new("flat array", steps),
+            &steps,
+            |b, &steps| b.iter(|| elf_sequence(&init, steps)),
+        );
+        group.bench_with_input(BenchmarkId::new("hashmap", steps), &steps, |b, &steps| {
+            b.iter(|| elf_sequence_hashmap(&init, steps))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, compare_engines);
+criterion_main!(benches);
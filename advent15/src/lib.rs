@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+use std::num::ParseIntError;
+
+/// Marks a number in `last_spoken` that hasn't been spoken yet.
+const NEVER: u32 = u32::MAX;
+
+/// Play the elves' memory game, starting with the numbers in `init`, and return the
+/// number spoken on turn `steps`. Turns are numbered from 1; nothing has been spoken
+/// by turn 0, which gives 0.
+pub fn elf_sequence(init: &[usize], steps: usize) -> usize {
+    elf_sequence_from(init.iter().copied(), steps).unwrap_or(0)
+}
+
+/// The same as `elf_sequence`, but the starting numbers can come from any iterator,
+/// and there's no answer for turn 0.
+///
+/// This uses the same flat array as `ElfSequence`, but it knows how many turns will be
+/// played, so it makes the array big enough up front and skips the iterator.
+pub fn elf_sequence_from<I: IntoIterator<Item = usize>>(init: I, steps: usize) -> Option<usize> {
+    let init: Vec<usize> = init.into_iter().collect();
+    if steps == 0 {
+        return None;
+    }
+    if steps <= init.len() {
+        return Some(init[steps - 1]);
+    }
+    let size = init.iter().map(|&num| num + 1).fold(steps, usize::max);
+    if size >= NEVER as usize {
+        // Turn numbers won't fit in a u32, so fall back on the slower way
+        return Some(elf_sequence_hashmap(&init, steps));
+    }
+
+    let mut last_spoken: Vec<u32> = vec![NEVER; size];
+    let mut current: usize = 0;
+    for step in 0..(steps - 1) {
+        if step < init.len() {
+            current = init[step];
+        }
+        current = speak(&mut last_spoken, current, step).0;
+    }
+    Some(current)
+}
+
+/// Say `current` on turn `step`, remembering it in `last_spoken`, and work out the
+/// number to say next: 0 if `current` is new, or how many turns ago it was last
+/// spoken. Also returns whether `current` is new.
+#[inline]
+fn speak(last_spoken: &mut [u32], current: usize, step: usize) -> (usize, bool) {
+    let last_time = std::mem::replace(&mut last_spoken[current], step as u32);
+    if last_time == NEVER {
+        (0, true)
+    } else {
+        (step - last_time as usize, false)
+    }
+}
+
+/// The numbers spoken in the elves' memory game, one per turn, starting with the
+/// numbers in `init`.
+///
+/// This keeps track of when each number was last spoken in a flat array, indexed by
+/// the number. Every number spoken after the starting ones is a gap between two turns,
+/// so the array only has to be as big as the number of turns so far (or the largest
+/// starting number). Turns are stored as u32s, so the sequence ends after about four
+/// billion turns.
+#[derive(Debug, Clone)]
+pub struct ElfSequence {
+    init: Vec<usize>,
+    last_spoken: Vec<u32>,
+    turn: usize,
+    next: usize,
+    distinct: usize,
+}
+
+impl ElfSequence {
+    pub fn new(init: Vec<usize>) -> Self {
+        ElfSequence::with_capacity(init, 0)
+    }
+
+    /// Start a sequence with room for numbers up to `capacity` without reallocating.
+    pub fn with_capacity(init: Vec<usize>, capacity: usize) -> Self {
+        ElfSequence {
+            init,
+            last_spoken: vec![NEVER; capacity],
+            turn: 0,
+            next: 0,
+            distinct: 0,
+        }
+    }
+
+    /// How many turns have been played.
+    pub fn turn(&self) -> usize {
+        self.turn
+    }
+
+    /// How many different numbers have been spoken so far.
+    pub fn distinct_spoken(&self) -> usize {
+        self.distinct
+    }
+}
+
+impl Iterator for ElfSequence {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let step = self.turn;
+        if step >= NEVER as usize {
+            return None;
+        }
+        // In the initial steps, the number comes from the init sequence
+        let current = self.init.get(step).copied().unwrap_or(self.next);
+        if current >= self.last_spoken.len() {
+            let size = (current + 1).max(self.last_spoken.len() * 2);
+            self.last_spoken.resize(size, NEVER);
+        }
+        let (next, is_new) = speak(&mut self.last_spoken, current, step);
+        self.next = next;
+        if is_new {
+            self.distinct += 1;
+        }
+        self.turn += 1;
+        Some(current)
+    }
+}
+
+/// The first turn (counting from 1) on which `value` is spoken, looking at most
+/// `max_turns` turns ahead.
+pub fn first_turn_spoken(init: &[usize], value: usize, max_turns: usize) -> Option<usize> {
+    ElfSequence::new(init.to_vec())
+        .take(max_turns)
+        .position(|num| num == value)
+        .map(|index| index + 1)
+}
+
+/// How many different numbers have been spoken by the end of turn `turns`.
+pub fn distinct_by_turn(init: &[usize], turns: usize) -> usize {
+    let mut sequence = ElfSequence::with_capacity(init.to_vec(), turns);
+    sequence.by_ref().take(turns).for_each(drop);
+    sequence.distinct_spoken()
+}
+
+/// Read starting numbers separated by commas or whitespace, like `0,3,6`.
+pub fn parse_starting_numbers(text: &str) -> Result<Vec<usize>, ParseIntError> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|token| !token.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+/// The original version of `elf_sequence`, which keeps track of when each number was
+/// last spoken in a HashMap. It's much slower, but it can handle any number of steps.
+pub fn elf_sequence_hashmap(init: &[usize], steps: usize) -> usize {
+    if steps == 0 {
+        return 0;
+    }
+    if steps <= init.len() {
+        // The loop below works out each turn's number from the turn before, which is
+        // wrong for turns that are still in the init sequence
+        return init[steps - 1];
+    }
+    // Keep track of when each given integer last occurred.
+    let mut last_spoken: HashMap<usize, usize> = HashMap::new();
+    let mut current: usize = 0;
+
+    for step in 0..(steps - 1) {
+        // if we're in the initial steps, override 'current' with the provided number
+        // from the init sequence
+        if step < init.len() {
+            current = init[step];
+        }
+
+        // Update the "last spoken" time for the current number to the current time step.
+        // This returns an Option of the old value, if any.
+        let maybe_last_time: Option<usize> = last_spoken.insert(current, step);
+        if let Some(last_time) = maybe_last_time {
+            // set "current" to the elapsed time since that number was last spoken
+            current = step - last_time;
+        } else {
+            current = 0;
+        }
+    }
+    current
+}
+
+#[test]
+fn test_elf_sequence() {
+    assert_eq!(elf_sequence(&[0, 3, 6], 10), 0);
+    assert_eq!(elf_sequence(&[1, 3, 2], 2020), 1);
+    assert_eq!(elf_sequence(&[2, 1, 3], 2020), 10);
+    assert_eq!(elf_sequence(&[3, 1, 2], 2020), 1836);
+    assert_eq!(elf_sequence(&[0, 3, 6], 2020), 436);
+}
+
+#[test]
+fn test_matches_hashmap() {
+    for init in [
+        vec![0, 3, 6],
+        vec![16, 11, 15, 0, 1, 7],
+        vec![5000, 2, 5000],
+    ] {
+        for steps in [0, 1, 2, 3, 4, 7, 8, 10, 2020] {
+            assert_eq!(
+                elf_sequence(&init, steps),
+                elf_sequence_hashmap(&init, steps)
+            );
+        }
+    }
+    assert_eq!(elf_sequence_from((0..7).step_by(3), 2020), Some(436));
+    assert_eq!(elf_sequence(&[0, 3, 6], 2), 3);
+}
+
+#[test]
+fn test_turn_zero() {
+    assert_eq!(elf_sequence_from(vec![0, 3, 6], 0), None);
+    assert_eq!(elf_sequence(&[0, 3, 6], 0), 0);
+    assert_eq!(elf_sequence(&[], 0), 0);
+    assert_eq!(elf_sequence(&[], 1), 0);
+}
+
+#[test]
+fn test_iterator() {
+    let spoken: Vec<usize> = ElfSequence::new(vec![0, 3, 6]).take(10).collect();
+    assert_eq!(spoken, vec![0, 3, 6, 0, 3, 3, 1, 0, 4, 0]);
+    assert_eq!(ElfSequence::new(vec![0, 3, 6]).nth(2019), Some(436));
+    assert_eq!(ElfSequence::new(vec![]).nth(1), Some(0));
+
+    assert_eq!(first_turn_spoken(&[0, 3, 6], 4, 2020), Some(9));
+    assert_eq!(first_turn_spoken(&[0, 3, 6], 6, 2020), Some(3));
+    assert_eq!(first_turn_spoken(&[0, 3, 6], 2, 10), None);
+    assert_eq!(distinct_by_turn(&[0, 3, 6], 10), 5);
+    assert_eq!(distinct_by_turn(&[0, 3, 6], 0), 0);
+}
+
+#[test]
+fn test_parse_starting_numbers() {
+    assert_eq!(parse_starting_numbers("0,3,6\n"), Ok(vec![0, 3, 6]));
+    assert_eq!(parse_starting_numbers("16 11, 15"), Ok(vec![16, 11, 15]));
+    assert!(parse_starting_numbers("1,two,3").is_err());
+}
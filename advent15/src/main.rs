@@ -1,44 +1,33 @@
-use std::collections::HashMap;
+use advent15::{parse_starting_numbers, ElfSequence};
+use std::env;
+use std::error::Error;
+use std::fs;
 
-fn elf_sequence(init: &[usize], steps: usize) -> usize {
-    // Keep track of when each given integer last occurred.
-    let mut last_spoken: HashMap<usize, usize> = HashMap::new();
-    let mut current: usize = 0;
-
-    for step in 0..(steps - 1) {
-        // if we're in the initial steps, override 'current' with the provided number
-        // from the init sequence
-        if step < init.len() {
-            current = init[step];
-        }
-
-        // Update the "last spoken" time for the current number to the current time step.
-        // This returns an Option of the old value, if any.
-        let maybe_last_time: Option<usize> = last_spoken.insert(current, step);
-        if let Some(last_time) = maybe_last_time {
-            // set "current" to the elapsed time since that number was last spoken
-            current = step - last_time;
-        } else {
-            current = 0;
+fn main() -> Result<(), Box<dyn Error>> {
+    // The starting numbers can be given on the command line, like `0,3,6`, or read
+    // from a file with `--file PATH`. Otherwise, use the numbers from the puzzle.
+    let args: Vec<String> = env::args().skip(1).collect();
+    let input: Vec<usize> = match args.first().map(String::as_str) {
+        Some("--file") => {
+            let path = args.get(1).ok_or("--file needs a path")?;
+            parse_starting_numbers(&fs::read_to_string(path)?)?
         }
-    }
-    current
-}
+        Some(_) => parse_starting_numbers(&args.join(" "))?,
+        None => vec![16, 11, 15, 0, 1, 7],
+    };
 
-fn main() {
-    let input: Vec<usize> = vec![16, 11, 15, 0, 1, 7];
-    let result = elf_sequence(&input, 2020);
+    // Play the game once, and read both answers off the same sequence
+    let mut sequence = ElfSequence::with_capacity(input, 30_000_000);
+    let result = sequence.nth(2020 - 1).ok_or("the game ended early")?;
     println!("the 2020th number spoken was {}", result);
 
-    let result = elf_sequence(&input, 30_000_000);
+    let result = sequence
+        .nth(30_000_000 - 2020 - 1)
+        .ok_or("the game ended early")?;
     println!("the 30 millionth number spoken was {}", result);
-}
-
-#[test]
-fn test_elf_sequence() {
-    assert_eq!(elf_sequence(&[0, 3, 6], 10), 0);
-    assert_eq!(elf_sequence(&[1, 3, 2], 2020), 1);
-    assert_eq!(elf_sequence(&[2, 1, 3], 2020), 10);
-    assert_eq!(elf_sequence(&[3, 1, 2], 2020), 1836);
-    assert_eq!(elf_sequence(&[0, 3, 6], 2020), 436);
+    println!(
+        "{} different numbers were spoken by then",
+        sequence.distinct_spoken()
+    );
+    Ok(())
 }
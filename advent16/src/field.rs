@@ -0,0 +1,116 @@
+use std::ops::RangeInclusive;
+
+/// A rule for one field of a ticket: its name, and the ranges of values it allows.
+/// The ranges are kept sorted and merged, so that no two of them overlap or touch.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Field {
+    pub name: String,
+    ranges: Vec<RangeInclusive<u64>>,
+}
+
+impl Field {
+    pub fn new(name: &str, ranges: impl IntoIterator<Item = RangeInclusive<u64>>) -> Self {
+        let mut sorted: Vec<RangeInclusive<u64>> = ranges
+            .into_iter()
+            .filter(|range| !range.is_empty())
+            .collect();
+        sorted.sort_by_key(|range| *range.start());
+
+        let mut merged: Vec<RangeInclusive<u64>> = Vec::with_capacity(sorted.len());
+        for range in sorted {
+            match merged.last_mut() {
+                // Merge ranges that overlap, or that are next to each other
+                Some(last) if *range.start() <= last.end().saturating_add(1) => {
+                    if range.end() > last.end() {
+                        *last = *last.start()..=*range.end();
+                    }
+                }
+                _ => merged.push(range),
+            }
+        }
+        Field {
+            name: name.to_string(),
+            ranges: merged,
+        }
+    }
+
+    /// Parse a line like `class: 1-3 or 5-7`, which can have any number of ranges
+    /// joined by `or`.
+    pub fn parse(input: &str) -> Self {
+        let (name, terms) = input
+            .split_once(':')
+            .unwrap_or_else(|| panic!("No ':' after the field name: {:?}", input));
+        let ranges = terms.split(" or ").map(|term| {
+            let (lower, upper) = term
+                .trim()
+                .split_once('-')
+                .unwrap_or_else(|| panic!("Not a range: {:?}", term));
+            let parse_bound = |bound: &str| -> u64 {
+                bound
+                    .trim()
+                    .parse()
+                    .unwrap_or_else(|_| panic!("Not a number: {:?}", bound))
+            };
+            parse_bound(lower)..=parse_bound(upper)
+        });
+        Field::new(name.trim(), ranges)
+    }
+
+    /// The range that comes closest to containing the value, and how far outside it
+    /// the value is (which is 0 if it's inside). When the value is halfway between two
+    /// ranges, this gives the lower one. Fields with no ranges give None.
+    pub fn closest_range(&self, value: u64) -> Option<(&RangeInclusive<u64>, u64)> {
+        let after = self.ranges.partition_point(|range| *range.start() <= value);
+        let below = after
+            .checked_sub(1)
+            .map(|i| (&self.ranges[i], value.saturating_sub(*self.ranges[i].end())));
+        let above = self
+            .ranges
+            .get(after)
+            .map(|range| (range, range.start() - value));
+        match (below, above) {
+            (Some(below), Some(above)) if above.1 < below.1 => Some(above),
+            (Some(below), _) => Some(below),
+            (None, above) => above,
+        }
+    }
+
+    pub fn contains(&self, value: u64) -> bool {
+        // Find the last range that starts at or before the value
+        let after = self.ranges.partition_point(|range| *range.start() <= value);
+        after > 0 && value <= *self.ranges[after - 1].end()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let field = Field::parse("departure location: 25-80 or 90-961");
+        assert_eq!(field.name, "departure location");
+        assert_eq!(field.ranges, &[25..=80, 90..=961]);
+
+        let field = Field::parse("zone: 40-50 or 1-3 or 4-9 or 45-60");
+        assert_eq!(field.ranges, &[1..=9, 40..=60]);
+        let field = Field::parse("wagon: 7-7");
+        assert_eq!(field.ranges, &[7..=7]);
+    }
+
+    #[test]
+    fn test_contains() {
+        let field = Field::new("row", vec![6..=11, 33..=44, 50..=50]);
+        let allowed: Vec<u64> = (0..60).filter(|&value| field.contains(value)).collect();
+        let expected: Vec<u64> = (6..=11).chain(33..=44).chain(50..=50).collect();
+        assert_eq!(allowed, expected);
+        assert!(!Field::new("empty", vec![]).contains(0));
+
+        assert_eq!(field.closest_range(8), Some((&(6..=11), 0)));
+        assert_eq!(field.closest_range(2), Some((&(6..=11), 4)));
+        assert_eq!(field.closest_range(22), Some((&(6..=11), 11)));
+        assert_eq!(field.closest_range(23), Some((&(33..=44), 10)));
+        assert_eq!(field.closest_range(90), Some((&(50..=50), 40)));
+        assert_eq!(Field::new("empty", vec![]).closest_range(1), None);
+    }
+}
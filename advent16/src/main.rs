@@ -1,15 +1,17 @@
-use ndarray::{Array, Array2};
-use scan_fmt::scan_fmt;
-use std::fs::read_to_string;
+mod field;
+mod matching;
+mod records;
+mod report;
 
-#[derive(Clone, PartialEq, Debug)]
-struct Field {
-    name: String,
-    lower1: u64,
-    upper1: u64,
-    lower2: u64,
-    upper2: u64,
-}
+use field::Field;
+use matching::{all_assignments, NoAssignment};
+use ndarray::Array2;
+use records::{decode_tickets, header, records_to_csv, FieldMatcher};
+use regex::Regex;
+use report::{report_to_csv, report_to_json, validation_report};
+use std::env;
+use std::error::Error;
+use std::fs::{self, read_to_string};
 
 #[derive(Clone, PartialEq, Debug)]
 struct Ticket {
@@ -29,28 +31,8 @@ enum ParseState {
     NearbyTickets,
 }
 
-fn parse_field_description(input: &str) -> Field {
-    let (name, lower1, upper1, lower2, upper2) = scan_fmt!(
-        input,
-        "{/[a-zA-Z ]+/}: {d}-{d} or {d}-{d}",
-        String,
-        u64,
-        u64,
-        u64,
-        u64
-    )
-    .unwrap();
-    Field {
-        name,
-        lower1,
-        lower2,
-        upper1,
-        upper2,
-    }
-}
-
 fn parse_ticket(input: &str, mine: bool) -> Ticket {
-    let numeric_strs = input.split(",");
+    let numeric_strs = input.split(',');
     let values: Vec<u64> = numeric_strs.map(|numstr| numstr.parse().unwrap()).collect();
     Ticket { mine, values }
 }
@@ -60,14 +42,14 @@ fn parse_ticket_notes(input: &str) -> TicketNotes {
     let mut tickets: Vec<Ticket> = Vec::new();
     let mut state = ParseState::Fields;
 
-    for line in input.split("\n") {
-        if line != "" {
+    for line in input.split('\n') {
+        if !line.is_empty() {
             match state {
                 ParseState::Fields => {
                     if line == "your ticket:" {
                         state = ParseState::MyTicket
                     } else {
-                        let field = parse_field_description(&line);
+                        let field = Field::parse(line);
                         fields.push(field);
                     }
                 }
@@ -75,12 +57,12 @@ fn parse_ticket_notes(input: &str) -> TicketNotes {
                     if line == "nearby tickets:" {
                         state = ParseState::NearbyTickets
                     } else {
-                        let ticket = parse_ticket(&line, true);
+                        let ticket = parse_ticket(line, true);
                         tickets.push(ticket);
                     }
                 }
                 ParseState::NearbyTickets => {
-                    let ticket = parse_ticket(&line, false);
+                    let ticket = parse_ticket(line, false);
                     tickets.push(ticket);
                 }
             }
@@ -89,18 +71,8 @@ fn parse_ticket_notes(input: &str) -> TicketNotes {
     TicketNotes { fields, tickets }
 }
 
-fn satisfies_field_constraint(field: &Field, value: u64) -> bool {
-    (value >= field.lower1 && value <= field.upper1)
-        || (value >= field.lower2 && value <= field.upper2)
-}
-
 fn satisfies_any_field(fields: &[Field], value: u64) -> bool {
-    for field in fields {
-        if satisfies_field_constraint(field, value) {
-            return true;
-        }
-    }
-    false
+    fields.iter().any(|field| field.contains(value))
 }
 
 fn scan_error_rate(notes: &TicketNotes) -> u64 {
@@ -139,80 +111,117 @@ fn filter_valid_tickets(notes: &TicketNotes) -> TicketNotes {
     }
 }
 
-fn find_field_order(notes: &TicketNotes) -> Vec<usize> {
-    let ticket_size = notes.fields.len();
-    assert_eq!(ticket_size, notes.tickets[0].values.len());
-    let mut constraint_grid: Array2<u32> = Array::ones((ticket_size, ticket_size));
-
-    // Make a table of which fields can have which indices
-    for row in 0..ticket_size {
-        for col in 0..ticket_size {
-            let field = &notes.fields[row];
-            for ticket in &notes.tickets {
-                if !ticket.mine {
-                    if !satisfies_field_constraint(field, ticket.values[col]) {
-                        constraint_grid[(row, col)] = 0;
-                        break;
-                    }
-                }
-            }
-        }
-    }
-    println!("{:?}", constraint_grid);
-    // In the constraint grid, rows are which field to consider,
-    // and columns are where they could be in order.
-
-    for _iter in 0..ticket_size {
-        for row in 0..ticket_size {
-            let sum: u32 = constraint_grid.row(row).sum();
-            if sum == 0 {
-                panic!("Field {} has no possible locations", row);
-            } else if sum == 1 {
-                let row_content = constraint_grid.row(row);
-                let unique_col = row_content.iter().position(|&val| val == 1).unwrap();
-                // println!("Field {} must be in position {}", row, unique_col);
-                for other_row in 0..ticket_size {
-                    if other_row != row {
-                        constraint_grid[(other_row, unique_col)] = 0;
-                    }
-                }
+/// Which fields can go in which positions: rows are fields, and columns are
+/// positions on the ticket. A field can go in a position if every nearby ticket has a
+/// value there that the field allows.
+fn constraint_grid(notes: &TicketNotes) -> Array2<bool> {
+    let num_fields = notes.fields.len();
+    let ticket_size = notes.tickets[0].values.len();
+    Array2::from_shape_fn((num_fields, ticket_size), |(row, col)| {
+        let field = &notes.fields[row];
+        notes
+            .tickets
+            .iter()
+            .filter(|ticket| !ticket.mine)
+            .all(|ticket| field.contains(ticket.values[col]))
+    })
+}
+
+/// The possible orders of the fields, up to `limit` of them, each giving the position
+/// of every field on the tickets.
+fn find_field_orders(notes: &TicketNotes, limit: usize) -> Result<Vec<Vec<usize>>, NoAssignment> {
+    all_assignments(&constraint_grid(notes), limit)
+}
+
+/// Describe why there's no field order, using the names of the fields.
+fn explain_no_order(err: &NoAssignment, fields: &[Field]) -> String {
+    let names: Vec<&str> = err
+        .fields
+        .iter()
+        .map(|&index| fields[index].name.as_str())
+        .collect();
+    format!(
+        "no valid field order: the fields {:?} can only go in positions {:?}",
+        names, err.positions
+    )
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // `--report csv` or `--report json` writes a list of the invalid tickets to a file,
+    // and `--decoded` writes all the valid tickets with their fields labeled.
+    // `--sum REGEX` and `--product REGEX` combine the fields of my ticket whose names
+    // match.
+    let mut report_format: Option<String> = None;
+    let mut write_decoded = false;
+    let mut queries: Vec<(String, FieldMatcher)> = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--report" => report_format = Some(args.next().ok_or("--report needs a format")?),
+            "--decoded" => write_decoded = true,
+            "--sum" | "--product" => {
+                let pattern = args.next().ok_or("queries need a regular expression")?;
+                queries.push((arg.clone(), FieldMatcher::Pattern(Regex::new(&pattern)?)));
             }
+            other => return Err(format!("unknown option: {}", other).into()),
         }
     }
-    println!("{:?}", constraint_grid);
-
-    let mut field_positions: Vec<usize> = Vec::new();
-    for row in 0..ticket_size {
-        let unique_col = constraint_grid
-            .row(row)
-            .iter()
-            .position(|&val| val == 1)
-            .unwrap();
-        field_positions.push(unique_col);
-    }
-    field_positions
-}
 
-fn main() {
-    let input = read_to_string("input.txt").unwrap();
+    let input = read_to_string("input.txt")?;
     let notes = parse_ticket_notes(&input);
     println!("Scan error rate: {}", scan_error_rate(&notes));
 
-    let revised_notes = filter_valid_tickets(&notes);
-    let field_positions = find_field_order(&revised_notes);
+    let report = validation_report(&notes);
+    println!("{} nearby tickets are invalid", report.len());
+    match report_format.as_deref() {
+        Some("csv") => fs::write("invalid-tickets.csv", report_to_csv(&report))?,
+        Some("json") => fs::write("invalid-tickets.json", report_to_json(&report))?,
+        Some(other) => return Err(format!("unknown report format: {}", other).into()),
+        None => {}
+    }
 
-    let my_ticket = &revised_notes.tickets[0];
-    assert!(my_ticket.mine);
+    let revised_notes = filter_valid_tickets(&notes);
+    let orders = find_field_orders(&revised_notes, 10)
+        .map_err(|err| explain_no_order(&err, &revised_notes.fields))?;
+    if orders.len() > 1 {
+        println!(
+            "The field order is ambiguous; here are {} of the possibilities",
+            orders.len()
+        );
+    }
 
-    let mut product: u64 = 1;
-    for (i, field) in revised_notes.fields.iter().enumerate() {
-        if field.name.starts_with("departure ") {
-            let val = my_ticket.values[field_positions[i]];
-            product *= val;
-            println!("{}: {}", field.name, val);
+    let departure = FieldMatcher::Prefix("departure ".to_string());
+    for field_positions in &orders {
+        let records = decode_tickets(&revised_notes, field_positions);
+        let my_ticket = &records[0];
+        assert!(my_ticket.mine);
+        for (name, val) in my_ticket.matching(&departure) {
+            println!("{}: {}", name, val);
+        }
+        match my_ticket.product_where(&departure) {
+            Some(product) => println!("product: {}", product),
+            None => println!("product: too big for a u64"),
+        }
+        for (query, matcher) in &queries {
+            let result = if query == "--sum" {
+                my_ticket.sum_where(matcher)
+            } else {
+                my_ticket.product_where(matcher)
+            };
+            match result {
+                Some(result) => println!("{} of {}: {}", &query[2..], matcher, result),
+                None => println!("{} of {}: too big for a u64", &query[2..], matcher),
+            }
         }
     }
-    println!("product: {}", product);
+
+    if write_decoded {
+        let field_positions = &orders[0];
+        let records = decode_tickets(&revised_notes, field_positions);
+        let csv = records_to_csv(&header(&revised_notes, field_positions), &records);
+        fs::write("decoded-tickets.csv", csv)?;
+    }
+    Ok(())
 }
 
 #[test]
@@ -235,3 +244,55 @@ nearby tickets:
     let revised_notes = filter_valid_tickets(&notes);
     assert_eq!(revised_notes.tickets.len(), 2);
 }
+
+#[test]
+fn test_more_ranges() {
+    let input = "class: 1-3 or 5-7 or 60-61
+row: 6-11 or 33-44
+seat: 13-40 or 45-50 or 55-55 or 70-80
+
+your ticket:
+7,1,14
+
+nearby tickets:
+7,3,47
+40,4,50
+55,2,20
+38,6,12
+61,70,9";
+    let notes = parse_ticket_notes(input);
+    assert_eq!(scan_error_rate(&notes), 4 + 12);
+}
+
+#[test]
+fn test_field_order() {
+    let input = "class: 0-1 or 4-19
+row: 0-5 or 8-19
+seat: 0-13 or 16-19
+
+your ticket:
+11,12,13
+
+nearby tickets:
+3,9,18
+15,1,5
+5,14,9";
+    let notes = filter_valid_tickets(&parse_ticket_notes(input));
+    assert_eq!(find_field_orders(&notes, 10), Ok(vec![vec![1, 0, 2]]));
+
+    let input = "class: 1-3
+row: 1-3
+seat: 5-7
+
+your ticket:
+1,2,3
+
+nearby tickets:
+1,2,3";
+    let notes = parse_ticket_notes(input);
+    let err = find_field_orders(&notes, 10).unwrap_err();
+    assert_eq!(
+        explain_no_order(&err, &notes.fields),
+        "no valid field order: the fields [\"seat\"] can only go in positions []"
+    );
+}
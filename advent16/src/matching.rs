@@ -0,0 +1,280 @@
+// Assigning ticket fields to positions is a bipartite matching problem: there's an
+// edge from each field to each position where every valid ticket has a value the
+// field allows. A valid field order is a matching that covers every field.
+
+use ndarray::Array2;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+
+/// Why no field order exists: a group of fields that can only go in fewer positions
+/// than there are fields in the group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoAssignment {
+    pub fields: Vec<usize>,
+    pub positions: Vec<usize>,
+}
+
+impl fmt::Display for NoAssignment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "fields {:?} can only go in positions {:?}, which aren't enough for all of them",
+            self.fields, self.positions
+        )
+    }
+}
+
+impl Error for NoAssignment {}
+
+const UNMATCHED: usize = usize::MAX;
+
+/// A maximum matching in the grid, where rows are fields and columns are positions.
+/// The result gives the position matched to each field, if there is one.
+///
+/// This is the Hopcroft–Karp algorithm: each phase does a breadth-first search from
+/// the unmatched fields to find the length of the shortest augmenting paths, then a
+/// depth-first search along those layers to apply as many of them as it can at once.
+pub fn maximum_matching(grid: &Array2<bool>) -> Vec<Option<usize>> {
+    let (n_fields, n_positions) = grid.dim();
+    let edges: Vec<Vec<usize>> = (0..n_fields)
+        .map(|field| (0..n_positions).filter(|&pos| grid[(field, pos)]).collect())
+        .collect();
+    let mut field_match = vec![UNMATCHED; n_fields];
+    let mut position_match = vec![UNMATCHED; n_positions];
+    let mut layer = vec![0usize; n_fields];
+
+    loop {
+        // Label each field with its distance from an unmatched field
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        for field in 0..n_fields {
+            if field_match[field] == UNMATCHED {
+                layer[field] = 0;
+                queue.push_back(field);
+            } else {
+                layer[field] = usize::MAX;
+            }
+        }
+        let mut found_path = false;
+        while let Some(field) = queue.pop_front() {
+            for &pos in &edges[field] {
+                let next = position_match[pos];
+                if next == UNMATCHED {
+                    found_path = true;
+                } else if layer[next] == usize::MAX {
+                    layer[next] = layer[field] + 1;
+                    queue.push_back(next);
+                }
+            }
+        }
+        if !found_path {
+            break;
+        }
+        for field in 0..n_fields {
+            if field_match[field] == UNMATCHED {
+                augment(
+                    field,
+                    &edges,
+                    &mut layer,
+                    &mut field_match,
+                    &mut position_match,
+                );
+            }
+        }
+    }
+
+    field_match
+        .into_iter()
+        .map(|pos| if pos == UNMATCHED { None } else { Some(pos) })
+        .collect()
+}
+
+/// Look for an augmenting path from `field` that follows the BFS layers, and flip the
+/// matching along it if there is one.
+fn augment(
+    field: usize,
+    edges: &[Vec<usize>],
+    layer: &mut [usize],
+    field_match: &mut [usize],
+    position_match: &mut [usize],
+) -> bool {
+    for &pos in &edges[field] {
+        let next = position_match[pos];
+        let extends = next == UNMATCHED
+            || (layer[next] == layer[field] + 1
+                && augment(next, edges, layer, field_match, position_match));
+        if extends {
+            field_match[field] = pos;
+            position_match[pos] = field;
+            return true;
+        }
+    }
+    // Nothing from here works, so don't try this field again in this phase
+    layer[field] = usize::MAX;
+    false
+}
+
+/// Find a group of fields that shows a maximum matching can't be extended to cover
+/// them all, starting from a field the matching left out. Every field reachable from
+/// it by alternating paths is in the group, and so are all their possible positions,
+/// each of which is already matched to another field in the group.
+fn explain_failure(grid: &Array2<bool>, matching: &[Option<usize>]) -> NoAssignment {
+    let (n_fields, n_positions) = grid.dim();
+    let start = matching
+        .iter()
+        .position(Option::is_none)
+        .expect("only called when some field is unmatched");
+    let mut position_match = vec![UNMATCHED; n_positions];
+    for (field, pos) in matching.iter().enumerate() {
+        if let Some(pos) = pos {
+            position_match[*pos] = field;
+        }
+    }
+
+    let mut seen_fields = vec![false; n_fields];
+    let mut seen_positions = vec![false; n_positions];
+    let mut queue = VecDeque::from(vec![start]);
+    seen_fields[start] = true;
+    while let Some(field) = queue.pop_front() {
+        for pos in 0..n_positions {
+            if grid[(field, pos)] && !seen_positions[pos] {
+                seen_positions[pos] = true;
+                let next = position_match[pos];
+                if !seen_fields[next] {
+                    seen_fields[next] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    NoAssignment {
+        fields: (0..n_fields).filter(|&field| seen_fields[field]).collect(),
+        positions: (0..n_positions)
+            .filter(|&pos| seen_positions[pos])
+            .collect(),
+    }
+}
+
+/// Every way to give each field its own position, up to `limit` of them, as a list of
+/// positions indexed by field. If there's no way, explain why.
+///
+/// This branches on the field with the fewest choices, and checks with a maximum
+/// matching that each branch can still be completed, so it never goes down a dead
+/// end. Grids with very few constraints can have a huge number of assignments, which
+/// is what the limit is for.
+pub fn all_assignments(grid: &Array2<bool>, limit: usize) -> Result<Vec<Vec<usize>>, NoAssignment> {
+    let matching = maximum_matching(grid);
+    if matching.iter().any(Option::is_none) {
+        return Err(explain_failure(grid, &matching));
+    }
+    let mut grid = grid.clone();
+    let mut assigned: Vec<Option<usize>> = vec![None; grid.nrows()];
+    let mut results = Vec::new();
+    extend_assignments(&mut grid, &mut assigned, limit, &mut results);
+    Ok(results)
+}
+
+fn extend_assignments(
+    grid: &mut Array2<bool>,
+    assigned: &mut Vec<Option<usize>>,
+    limit: usize,
+    results: &mut Vec<Vec<usize>>,
+) {
+    if results.len() >= limit {
+        return;
+    }
+    let choices = |field: usize| grid.row(field).iter().filter(|&&ok| ok).count();
+    let next_field = (0..assigned.len())
+        .filter(|&field| assigned[field].is_none())
+        .min_by_key(|&field| choices(field));
+    let field = match next_field {
+        Some(field) => field,
+        None => {
+            results.push(assigned.iter().map(|pos| pos.unwrap()).collect());
+            return;
+        }
+    };
+
+    let options: Vec<usize> = (0..grid.ncols())
+        .filter(|&pos| grid[(field, pos)])
+        .collect();
+    for pos in options {
+        // Take the field's row and the position's column out of the grid, except for
+        // the edge between them
+        let saved_row = grid.row(field).to_owned();
+        let saved_col = grid.column(pos).to_owned();
+        grid.row_mut(field).fill(false);
+        grid.column_mut(pos).fill(false);
+        grid[(field, pos)] = true;
+        assigned[field] = Some(pos);
+
+        if maximum_matching(grid).iter().all(Option::is_some) {
+            extend_assignments(grid, assigned, limit, results);
+        }
+
+        assigned[field] = None;
+        grid.row_mut(field).assign(&saved_row);
+        grid.column_mut(pos).assign(&saved_col);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn grid(rows: &[&str]) -> Array2<bool> {
+        let n_cols = rows[0].len();
+        let cells: Vec<bool> = rows
+            .iter()
+            .flat_map(|row| row.chars().map(|c| c == '#'))
+            .collect();
+        Array2::from_shape_vec((rows.len(), n_cols), cells).unwrap()
+    }
+
+    #[test]
+    fn test_unique() {
+        // The puzzle's example: row, class and seat
+        let g = grid(&["###", ".##", "..#"]);
+        assert_eq!(maximum_matching(&g), vec![Some(0), Some(1), Some(2)]);
+        assert_eq!(all_assignments(&g, 10), Ok(vec![vec![0, 1, 2]]));
+    }
+
+    #[test]
+    fn test_ambiguous() {
+        // Eliminating fields with only one choice gets nowhere here
+        let g = grid(&["##..", "##..", "..##", "..##"]);
+        let mut orders = all_assignments(&g, 10).unwrap();
+        orders.sort();
+        assert_eq!(
+            orders,
+            vec![
+                vec![0, 1, 2, 3],
+                vec![0, 1, 3, 2],
+                vec![1, 0, 2, 3],
+                vec![1, 0, 3, 2]
+            ]
+        );
+        assert_eq!(all_assignments(&g, 3).unwrap().len(), 3);
+        assert_eq!(all_assignments(&grid(&["####"; 4]), 100).unwrap().len(), 24);
+    }
+
+    #[test]
+    fn test_impossible() {
+        let g = grid(&["#..", ".##", "#..", "..#"]);
+        assert_eq!(
+            all_assignments(&g, 10),
+            Err(NoAssignment {
+                fields: vec![0, 2],
+                positions: vec![0],
+            })
+        );
+        let g = grid(&["##.", "...", "###"]);
+        assert_eq!(
+            all_assignments(&g, 10),
+            Err(NoAssignment {
+                fields: vec![1],
+                positions: vec![],
+            })
+        );
+    }
+}
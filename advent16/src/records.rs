@@ -0,0 +1,195 @@
+use crate::report::csv_cell;
+use crate::TicketNotes;
+use regex::Regex;
+use std::fmt::{self, Write};
+
+/// A ticket with its values labeled by field name, in the order they appear on the
+/// ticket.
+#[derive(Clone, PartialEq, Debug)]
+pub struct TicketRecord {
+    pub mine: bool,
+    pub values: Vec<(String, u64)>,
+}
+
+/// A way of picking out fields by name.
+#[derive(Clone, Debug)]
+pub enum FieldMatcher {
+    Prefix(String),
+    Pattern(Regex),
+}
+
+impl FieldMatcher {
+    pub fn matches(&self, name: &str) -> bool {
+        match self {
+            FieldMatcher::Prefix(prefix) => name.starts_with(prefix.as_str()),
+            FieldMatcher::Pattern(regex) => regex.is_match(name),
+        }
+    }
+}
+
+impl fmt::Display for FieldMatcher {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FieldMatcher::Prefix(prefix) => write!(f, "{}*", prefix),
+            FieldMatcher::Pattern(regex) => write!(f, "/{}/", regex),
+        }
+    }
+}
+
+impl TicketRecord {
+    /// The fields whose names match, with their values.
+    pub fn matching<'a>(
+        &'a self,
+        matcher: &'a FieldMatcher,
+    ) -> impl Iterator<Item = (&'a str, u64)> + 'a {
+        self.values
+            .iter()
+            .filter(move |(name, _)| matcher.matches(name))
+            .map(|(name, value)| (name.as_str(), *value))
+    }
+
+    /// The product of the fields whose names match, or None if it doesn't fit in a u64.
+    pub fn product_where(&self, matcher: &FieldMatcher) -> Option<u64> {
+        self.matching(matcher)
+            .try_fold(1u64, |product, (_, value)| product.checked_mul(value))
+    }
+
+    /// The sum of the fields whose names match, or None if it doesn't fit in a u64.
+    pub fn sum_where(&self, matcher: &FieldMatcher) -> Option<u64> {
+        self.matching(matcher)
+            .try_fold(0u64, |sum, (_, value)| sum.checked_add(value))
+    }
+}
+
+/// The field names in the order they appear on the tickets, given the position of
+/// each field. Tickets can have more positions than there are fields, and those
+/// positions get an empty name.
+pub fn header(notes: &TicketNotes, field_positions: &[usize]) -> Vec<String> {
+    let ticket_size = notes
+        .tickets
+        .iter()
+        .map(|ticket| ticket.values.len())
+        .chain(field_positions.iter().map(|&pos| pos + 1))
+        .max()
+        .unwrap_or(0);
+    let mut names = vec![String::new(); ticket_size];
+    for (field, &pos) in notes.fields.iter().zip(field_positions) {
+        names[pos] = field.name.clone();
+    }
+    names
+}
+
+/// Label the values on every ticket in the notes, which should already have had the
+/// invalid tickets taken out.
+pub fn decode_tickets(notes: &TicketNotes, field_positions: &[usize]) -> Vec<TicketRecord> {
+    let names = header(notes, field_positions);
+    notes
+        .tickets
+        .iter()
+        .map(|ticket| TicketRecord {
+            mine: ticket.mine,
+            values: names
+                .iter()
+                .cloned()
+                .zip(ticket.values.iter().copied())
+                .collect(),
+        })
+        .collect()
+}
+
+/// One row per ticket, with a header row of field names in ticket order.
+pub fn records_to_csv(header: &[String], records: &[TicketRecord]) -> String {
+    let cells: Vec<String> = header.iter().map(|name| csv_cell(name)).collect();
+    let mut csv = cells.join(",") + "\n";
+    for record in records {
+        let values: Vec<String> = record
+            .values
+            .iter()
+            .map(|(_, value)| value.to_string())
+            .collect();
+        writeln!(csv, "{}", values.join(",")).unwrap();
+    }
+    csv
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{filter_valid_tickets, find_field_orders, parse_ticket_notes};
+
+    #[test]
+    fn test_records() {
+        let input = "class: 0-1 or 4-19
+departure row: 0-5 or 8-19
+departure seat: 0-13 or 16-19
+
+your ticket:
+11,12,13
+
+nearby tickets:
+3,9,18
+15,1,5
+5,14,9
+20,20,20";
+        let notes = filter_valid_tickets(&parse_ticket_notes(input));
+        let order = &find_field_orders(&notes, 1).unwrap()[0];
+        let names = header(&notes, order);
+        assert_eq!(names, vec!["departure row", "class", "departure seat"]);
+
+        let records = decode_tickets(&notes, order);
+        assert_eq!(records.len(), 4);
+        let mine = &records[0];
+        assert!(mine.mine);
+        assert_eq!(mine.values[1], ("class".to_string(), 12));
+
+        let departure = FieldMatcher::Prefix("departure".to_string());
+        assert_eq!(mine.product_where(&departure), Some(11 * 13));
+        assert_eq!(mine.sum_where(&departure), Some(11 + 13));
+        let seats = FieldMatcher::Pattern(Regex::new("(seat|class)$").unwrap());
+        assert_eq!(mine.sum_where(&seats), Some(12 + 13));
+
+        let huge = TicketRecord {
+            mine: true,
+            values: vec![
+                ("a".to_string(), u64::MAX / 2),
+                ("b".to_string(), 3),
+                ("c".to_string(), u64::MAX / 2),
+            ],
+        };
+        let all = FieldMatcher::Prefix(String::new());
+        assert_eq!(huge.product_where(&all), None);
+        assert_eq!(huge.sum_where(&all), None);
+        let just_b = FieldMatcher::Prefix("b".to_string());
+        assert_eq!(huge.product_where(&just_b), Some(3));
+
+        let csv = records_to_csv(&names, &records);
+        assert_eq!(
+            csv,
+            "departure row,class,departure seat\n11,12,13\n3,9,18\n15,1,5\n5,14,9\n"
+        );
+    }
+
+    #[test]
+    fn test_extra_positions() {
+        let input = "class: 0-1 or 4-19
+seat: 0-13 or 16-19
+
+your ticket:
+11,12,13
+
+nearby tickets:
+3,9,18
+15,1,5
+5,14,9";
+        let notes = filter_valid_tickets(&parse_ticket_notes(input));
+        let order = &find_field_orders(&notes, 1).unwrap()[0];
+        let names = header(&notes, order);
+        assert_eq!(names.len(), 3);
+        assert_eq!(names.iter().filter(|name| name.is_empty()).count(), 1);
+        assert!(names.contains(&"class".to_string()));
+        assert!(names.contains(&"seat".to_string()));
+
+        let records = decode_tickets(&notes, order);
+        assert_eq!(records[0].values.len(), 3);
+    }
+}
@@ -0,0 +1,236 @@
+use crate::{Field, TicketNotes};
+use std::fmt::Write;
+use std::ops::RangeInclusive;
+
+/// A field range that an invalid value came close to, and how far outside it it was.
+#[derive(Clone, PartialEq, Debug)]
+pub struct NearMiss {
+    pub field: String,
+    pub range: RangeInclusive<u64>,
+    pub distance: u64,
+}
+
+/// A value on a ticket that no field allows.
+#[derive(Clone, PartialEq, Debug)]
+pub struct InvalidValue {
+    pub position: usize,
+    pub value: u64,
+    /// The ranges that came closest to allowing the value. There's more than one when
+    /// several fields miss by the same amount.
+    pub closest: Vec<NearMiss>,
+}
+
+/// A nearby ticket with at least one invalid value. `index` counts the nearby tickets
+/// from 0, in the order they appear in the notes.
+#[derive(Clone, PartialEq, Debug)]
+pub struct InvalidTicket {
+    pub index: usize,
+    pub values: Vec<InvalidValue>,
+}
+
+fn near_misses(fields: &[Field], value: u64) -> Vec<NearMiss> {
+    let misses: Vec<NearMiss> = fields
+        .iter()
+        .filter_map(|field| {
+            field
+                .closest_range(value)
+                .map(|(range, distance)| NearMiss {
+                    field: field.name.clone(),
+                    range: range.clone(),
+                    distance,
+                })
+        })
+        .collect();
+    let best = misses.iter().map(|miss| miss.distance).min();
+    misses
+        .into_iter()
+        .filter(|miss| Some(miss.distance) == best)
+        .collect()
+}
+
+/// Every nearby ticket that has values no field allows, with those values.
+pub fn validation_report(notes: &TicketNotes) -> Vec<InvalidTicket> {
+    let nearby = notes.tickets.iter().filter(|ticket| !ticket.mine);
+    nearby
+        .enumerate()
+        .filter_map(|(index, ticket)| {
+            let values: Vec<InvalidValue> = ticket
+                .values
+                .iter()
+                .enumerate()
+                .filter(|&(_, &value)| !notes.fields.iter().any(|field| field.contains(value)))
+                .map(|(position, &value)| InvalidValue {
+                    position,
+                    value,
+                    closest: near_misses(&notes.fields, value),
+                })
+                .collect();
+            if values.is_empty() {
+                None
+            } else {
+                Some(InvalidTicket { index, values })
+            }
+        })
+        .collect()
+}
+
+/// Quote a CSV cell if it needs it.
+pub fn csv_cell(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Write a string as a JSON string literal.
+pub fn json_string(text: &str) -> String {
+    let mut json = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if (c as u32) < 0x20 => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn format_range(range: &RangeInclusive<u64>) -> String {
+    format!("{}-{}", range.start(), range.end())
+}
+
+/// One row per invalid value. When several ranges are equally close, they're all in
+/// the same cell, separated by semicolons.
+pub fn report_to_csv(report: &[InvalidTicket]) -> String {
+    let mut csv = String::from("ticket,position,value,closest_fields,closest_ranges,distance\n");
+    for ticket in report {
+        for invalid in &ticket.values {
+            let fields: Vec<&str> = invalid.closest.iter().map(|m| m.field.as_str()).collect();
+            let ranges: Vec<String> = invalid
+                .closest
+                .iter()
+                .map(|m| format_range(&m.range))
+                .collect();
+            let distance = invalid
+                .closest
+                .first()
+                .map_or(String::new(), |miss| miss.distance.to_string());
+            writeln!(
+                csv,
+                "{},{},{},{},{},{}",
+                ticket.index,
+                invalid.position,
+                invalid.value,
+                csv_cell(&fields.join(";")),
+                ranges.join(";"),
+                distance
+            )
+            .unwrap();
+        }
+    }
+    csv
+}
+
+/// A JSON list of invalid tickets, each with its list of invalid values.
+pub fn report_to_json(report: &[InvalidTicket]) -> String {
+    let tickets: Vec<String> = report
+        .iter()
+        .map(|ticket| {
+            let values: Vec<String> = ticket
+                .values
+                .iter()
+                .map(|invalid| {
+                    let closest: Vec<String> = invalid
+                        .closest
+                        .iter()
+                        .map(|miss| {
+                            format!(
+                                "{{\"field\":{},\"range\":[{},{}],\"distance\":{}}}",
+                                json_string(&miss.field),
+                                miss.range.start(),
+                                miss.range.end(),
+                                miss.distance
+                            )
+                        })
+                        .collect();
+                    format!(
+                        "{{\"position\":{},\"value\":{},\"closest\":[{}]}}",
+                        invalid.position,
+                        invalid.value,
+                        closest.join(",")
+                    )
+                })
+                .collect();
+            format!(
+                "{{\"ticket\":{},\"invalid_values\":[{}]}}",
+                ticket.index,
+                values.join(",")
+            )
+        })
+        .collect();
+    format!("[{}]\n", tickets.join(","))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse_ticket_notes;
+
+    const EXAMPLE: &str = "class: 1-3 or 5-7
+row: 6-11 or 33-44
+seat: 13-40 or 45-50
+
+your ticket:
+7,1,14
+
+nearby tickets:
+7,3,47
+40,4,50
+55,2,20
+38,6,12";
+
+    #[test]
+    fn test_report() {
+        let report = validation_report(&parse_ticket_notes(EXAMPLE));
+        let summary: Vec<(usize, usize, u64)> = report
+            .iter()
+            .flat_map(|t| t.values.iter().map(move |v| (t.index, v.position, v.value)))
+            .collect();
+        assert_eq!(summary, vec![(1, 1, 4), (2, 0, 55), (3, 2, 12)]);
+
+        // 4 is one away from both 1-3 and 5-7, which are in the same field
+        let closest = &report[0].values[0].closest;
+        assert_eq!(closest.len(), 1);
+        assert_eq!(closest[0].field, "class");
+        assert_eq!(closest[0].range, 1..=3);
+
+        // 12 is one away from both row and seat
+        let closest = &report[2].values[0].closest;
+        let fields: Vec<&str> = closest.iter().map(|m| m.field.as_str()).collect();
+        assert_eq!(fields, vec!["row", "seat"]);
+    }
+
+    #[test]
+    fn test_export() {
+        let report = validation_report(&parse_ticket_notes(EXAMPLE));
+        let csv = report_to_csv(&report);
+        assert_eq!(
+            csv.lines().collect::<Vec<&str>>(),
+            vec![
+                "ticket,position,value,closest_fields,closest_ranges,distance",
+                "1,1,4,class,1-3,1",
+                "2,0,55,seat,45-50,5",
+                "3,2,12,row;seat,6-11;13-40,1",
+            ]
+        );
+        let json = report_to_json(&report);
+        assert!(json.starts_with(
+            "[{\"ticket\":1,\"invalid_values\":[{\"position\":1,\"value\":4,\"closest\":[{\"field\":\"class\",\"range\":[1,3],\"distance\":1}]}]},"
+        ));
+        assert_eq!(csv_cell("a,b"), "\"a,b\"");
+        assert_eq!(json_string("say \"hi\""), "\"say \\\"hi\\\"\"");
+    }
+}
@@ -2,47 +2,75 @@ use defaultmap::DefaultHashMap;
 use std::collections::HashSet;
 use std::hash::Hash;
 
-// Define the HasNeighbors trait, so we can generalize over 3D and 4D Life-like
-// automatons. And beyond! Except we're not going beyond.
+// Define the HasNeighbors trait, so we can generalize over Life-like automatons in
+// any number of dimensions.
 
 trait HasNeighbors: Sized + Eq + Hash + Copy {
     fn neighbors(self) -> Vec<Self>;
 }
 
-impl HasNeighbors for (i32, i32, i32) {
-    fn neighbors(self) -> Vec<(i32, i32, i32)> {
-        let mut neighbor_list: Vec<(i32, i32, i32)> = Vec::new();
-        let (x, y, z) = self;
-        for nx in (x - 1)..=(x + 1) {
-            for ny in (y - 1)..=(y + 1) {
-                for nz in (z - 1)..=(z + 1) {
-                    if (nx, ny, nz) != (x, y, z) {
-                        neighbor_list.push((nx, ny, nz))
-                    }
-                }
+/// A point in D-dimensional space.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+struct Point<const D: usize>([i32; D]);
+
+impl<const D: usize> Point<D> {
+    /// Put a point from a 2D slice into D dimensions. The row and column become the
+    /// last two coordinates, and the others are 0.
+    fn from_2d(row: i32, col: i32) -> Self {
+        assert!(D >= 2, "a 2D slice doesn't fit in {} dimensions", D);
+        let mut coords = [0; D];
+        coords[D - 2] = row;
+        coords[D - 1] = col;
+        Point(coords)
+    }
+}
+
+impl<const D: usize> HasNeighbors for Point<D> {
+    // The Moore neighborhood: every point that differs by at most 1 in each coordinate.
+    fn neighbors(self) -> Vec<Point<D>> {
+        let count = 3usize.pow(D as u32);
+        let mut neighbor_list: Vec<Point<D>> = Vec::with_capacity(count - 1);
+        // Count in base 3, using each digit as the offset (plus one) of a coordinate
+        for code in 0..count {
+            let mut digits = code;
+            let mut neighbor = self;
+            for coord in neighbor.0.iter_mut() {
+                *coord += (digits % 3) as i32 - 1;
+                digits /= 3;
+            }
+            if neighbor != self {
+                neighbor_list.push(neighbor)
             }
         }
         neighbor_list
     }
 }
 
-impl HasNeighbors for (i32, i32, i32, i32) {
-    fn neighbors(self) -> Vec<(i32, i32, i32, i32)> {
-        let mut neighbor_list: Vec<(i32, i32, i32, i32)> = Vec::new();
-        let (x, y, z, w) = self;
-        for nx in (x - 1)..=(x + 1) {
-            for ny in (y - 1)..=(y + 1) {
-                for nz in (z - 1)..=(z + 1) {
-                    for nw in (w - 1)..=(w + 1) {
-                        if (nx, ny, nz, nw) != (x, y, z, w) {
-                            neighbor_list.push((nx, ny, nz, nw))
-                        }
-                    }
-                }
+/// The positions of the live cells in a 2D slice, where `#` is alive.
+fn parse_slice(input: &str) -> Vec<(i32, i32)> {
+    let mut cells = Vec::new();
+    for (row, line) in input.lines().enumerate() {
+        for (col, ch) in line.chars().enumerate() {
+            if ch == '#' {
+                cells.push((row as i32, col as i32));
             }
         }
-        neighbor_list
     }
+    cells
+}
+
+/// Embed the live cells of a 2D slice into D dimensions.
+fn embed_slice<const D: usize>(cells: &[(i32, i32)]) -> HashSet<Point<D>> {
+    cells
+        .iter()
+        .map(|&(row, col)| Point::from_2d(row, col))
+        .collect()
+}
+
+/// Run Life for `nsteps` steps in D dimensions, starting from a 2D slice, and return
+/// how many cells are alive at the end.
+fn count_after<const D: usize>(cells: &[(i32, i32)], nsteps: usize) -> usize {
+    run_nd_life(embed_slice::<D>(cells), nsteps).len()
 }
 
 fn step_nd_life<T: HasNeighbors>(grid: HashSet<T>) -> HashSet<T> {
@@ -78,44 +106,65 @@ fn run_nd_life<T: HasNeighbors>(grid: HashSet<T>, nsteps: usize) -> HashSet<T> {
 }
 
 fn main() {
-    let mut init_state_3d: HashSet<(i32, i32, i32)> = HashSet::new();
-    let mut init_state_4d: HashSet<(i32, i32, i32, i32)> = HashSet::new();
+    // Run in the dimensions given on the command line, or in 3 and 4 dimensions like
+    // the puzzle asks
+    let mut dimensions: Vec<usize> = std::env::args()
+        .skip(1)
+        .map(|arg| arg.parse().expect("dimensions should be numbers"))
+        .collect();
+    if dimensions.is_empty() {
+        dimensions = vec![3, 4];
+    }
+
     let input = std::fs::read_to_string("input.txt").unwrap();
-    let lines = input.split("\n");
-    for (row, line) in lines.enumerate() {
-        for (col, ch) in line.chars().enumerate() {
-            if ch == '#' {
-                init_state_3d.insert((0, row as i32, col as i32));
-                init_state_4d.insert((0, 0, row as i32, col as i32));
-            }
-        }
+    let cells = parse_slice(&input);
+    for dims in dimensions {
+        let count = match dims {
+            2 => count_after::<2>(&cells, 6),
+            3 => count_after::<3>(&cells, 6),
+            4 => count_after::<4>(&cells, 6),
+            5 => count_after::<5>(&cells, 6),
+            6 => count_after::<6>(&cells, 6),
+            7 => count_after::<7>(&cells, 6),
+            other => panic!("Can't run Life in {} dimensions", other),
+        };
+        println!("cells in {}d: {}", dims, count);
     }
-    let final_state = run_nd_life(init_state_3d, 6);
-    println!("cells in 3d: {}", final_state.len());
-    let final_state = run_nd_life(init_state_4d, 6);
-    println!("cells in 4d: {}", final_state.len());
 }
 
 #[test]
 fn test_glider_3d() {
-    let mut init_state: HashSet<(i32, i32, i32)> = HashSet::new();
-    init_state.insert((0, 0, 1));
-    init_state.insert((0, 1, 2));
-    init_state.insert((0, 2, 0));
-    init_state.insert((0, 2, 1));
-    init_state.insert((0, 2, 2));
+    let mut init_state: HashSet<Point<3>> = HashSet::new();
+    init_state.insert(Point([0, 0, 1]));
+    init_state.insert(Point([0, 1, 2]));
+    init_state.insert(Point([0, 2, 0]));
+    init_state.insert(Point([0, 2, 1]));
+    init_state.insert(Point([0, 2, 2]));
     let final_state = run_nd_life(init_state, 6);
     assert_eq!(final_state.len(), 112);
 }
 
 #[test]
 fn test_glider_4d() {
-    let mut init_state: HashSet<(i32, i32, i32, i32)> = HashSet::new();
-    init_state.insert((0, 0, 0, 1));
-    init_state.insert((0, 0, 1, 2));
-    init_state.insert((0, 0, 2, 0));
-    init_state.insert((0, 0, 2, 1));
-    init_state.insert((0, 0, 2, 2));
+    let mut init_state: HashSet<Point<4>> = HashSet::new();
+    init_state.insert(Point([0, 0, 0, 1]));
+    init_state.insert(Point([0, 0, 1, 2]));
+    init_state.insert(Point([0, 0, 2, 0]));
+    init_state.insert(Point([0, 0, 2, 1]));
+    init_state.insert(Point([0, 0, 2, 2]));
     let final_state = run_nd_life(init_state, 6);
     assert_eq!(final_state.len(), 848);
 }
+
+#[test]
+fn test_any_dimension() {
+    assert_eq!(Point([0, 0]).neighbors().len(), 8);
+    assert_eq!(Point([5, 5, 5, 5, 5]).neighbors().len(), 242);
+    assert_eq!(Point::<4>::from_2d(2, 1), Point([0, 0, 2, 1]));
+
+    let glider = parse_slice(".#.\n..#\n###\n");
+    assert_eq!(count_after::<3>(&glider, 6), 112);
+    // A glider in 2D is still a glider
+    assert_eq!(count_after::<2>(&glider, 6), 5);
+    assert_eq!(count_after::<5>(&glider, 6), 5760);
+}
@@ -1,4 +1,3 @@
-#[allow(dead_code)]
 use std::fs::File;
 use std::io;
 use std::io::{BufRead, BufReader, Lines};
@@ -8,11 +7,3 @@ pub fn read_lines_result(filename: &str) -> io::Result<Lines<impl BufRead>> {
     let reader = BufReader::new(file);
     Ok(reader.lines())
 }
-
-pub fn read_lines(filename: &str) -> Lines<impl BufRead> {
-    read_lines_result(filename).unwrap()
-}
-
-pub fn get_lines(filename: &str) -> Vec<String> {
-    read_lines(filename).map(|line| line.unwrap()).collect()
-}
@@ -1,81 +1,174 @@
 mod helpers;
-use helpers::get_lines;
-
-fn sum_pair_in_window(window: &[i64], sum: i64) -> bool {
-    // println!("window: {:?}\nnext: {}", window, sum);
-    for num1 in window {
-        for num2 in window {
-            if num1 != num2 && num1 + num2 == sum {
-                return true;
-            }
-        }
+mod xmas;
+use xmas::{process_numbers, read_numbers, ContiguousRanges, InvalidNumbers};
+
+use eyre::{eyre, Result};
+use std::collections::HashMap;
+use std::env;
+use std::io::{self, BufRead};
+use std::ops::Range;
+
+/// Find the first contiguous range of `seq` that adds up to `target`.
+///
+/// When all the numbers are non-negative, this slides a window along the sequence
+/// with two pointers. Otherwise it looks up running sums in a HashMap. Either way,
+/// it takes O(n) time.
+pub fn find_contiguous_range(seq: &[i64], target: i64) -> Option<Range<usize>> {
+    if target >= 0 && seq.iter().all(|&num| num >= 0) {
+        find_contiguous_range_nonnegative(seq, target)
+    } else {
+        find_contiguous_range_signed(seq, target)
     }
-    false
 }
 
-fn number_out_of_window(seq: &Vec<i64>, window_size: usize) -> i64 {
-    for offset in 0..(seq.len() - window_size) {
-        let window: &[i64] = &seq[offset..(offset + window_size)];
-        let next: i64 = seq[offset + window_size];
-        if !sum_pair_in_window(&window, next) {
-            return next;
+fn find_contiguous_range_nonnegative(seq: &[i64], target: i64) -> Option<Range<usize>> {
+    let mut start: usize = 0;
+    let mut total: i64 = 0;
+    for (end, num) in seq.iter().enumerate() {
+        total += num;
+        while total > target && start < end {
+            total -= seq[start];
+            start += 1;
+        }
+        if total == target {
+            return Some(start..(end + 1));
         }
     }
-    panic!("all the numbers are okay")
+    None
 }
 
-fn find_contiguous_sum(seq: &Vec<i64>, target: i64) -> i64 {
-    let mut running_sums: Vec<i64> = Vec::new();
+fn find_contiguous_range_signed(seq: &[i64], target: i64) -> Option<Range<usize>> {
+    // Map each running sum to the earliest position where it occurs
+    let mut first_sum_at: HashMap<i64, usize> = HashMap::new();
     let mut total: i64 = 0;
-    for num in seq {
-        running_sums.push(total);
+    first_sum_at.insert(total, 0);
+    for (end, num) in seq.iter().enumerate() {
         total += num;
+        if let Some(&start) = first_sum_at.get(&(total - target)) {
+            return Some(start..(end + 1));
+        }
+        first_sum_at.entry(total).or_insert(end + 1);
     }
-    running_sums.push(total);
-
-    let running_sums = running_sums;
-    for start in 0..(running_sums.len() - 2) {
-        for end in (start + 1)..running_sums.len() {
-            if running_sums[end] - running_sums[start] == target {
-                let slice: &[i64] = &seq[start..end];
-                let min = slice.iter().min().unwrap();
-                let max = slice.iter().max().unwrap();
-                return min + max;
+    None
+}
+
+fn find_contiguous_sum(seq: &[i64], target: i64) -> i64 {
+    let range = find_contiguous_range(seq, target).expect("No contiguous sum found");
+    let slice: &[i64] = &seq[range];
+    let min = slice.iter().min().unwrap();
+    let max = slice.iter().max().unwrap();
+    min + max
+}
+
+/// Command-line options: `--preamble N` sets the size of the preamble (25 by
+/// default), and `--file PATH` reads the numbers from a file instead of input.txt.
+/// A path of `-` reads them from stdin, so this can follow a live feed.
+struct Options {
+    preamble: usize,
+    path: String,
+}
+
+impl Options {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Result<Self> {
+        let mut options = Options {
+            preamble: 25,
+            path: String::from("input.txt"),
+        };
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--preamble" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| eyre!("--preamble needs a size"))?;
+                    options.preamble = value
+                        .parse()
+                        .map_err(|_| eyre!("preamble size {:?} is not a number", value))?;
+                }
+                "--file" => {
+                    options.path = args.next().ok_or_else(|| eyre!("--file needs a path"))?;
+                }
+                _ => return Err(eyre!("unknown option {:?}", arg)),
             }
         }
+        if options.preamble < 2 {
+            return Err(eyre!("the preamble needs at least two numbers"));
+        }
+        Ok(options)
     }
-    panic!("No contiguous sum found");
 }
 
-fn main() {
-    let seq: Vec<i64> = get_lines("input.txt")
-        .iter()
-        .map(|line| line.parse().unwrap())
-        .collect();
+fn main() -> Result<()> {
+    let options = Options::from_args(env::args().skip(1))?;
+    let lines: Box<dyn Iterator<Item = io::Result<String>>> = if options.path == "-" {
+        Box::new(io::stdin().lock().lines())
+    } else {
+        Box::new(helpers::read_lines_result(&options.path)?)
+    };
 
-    let num: i64 = number_out_of_window(&seq, 25);
+    // Report invalid numbers as they arrive, while keeping the sequence for part 2
+    let mut seq: Vec<i64> = Vec::new();
+    let invalid: Vec<i64> = process_numbers(read_numbers(lines), |nums| {
+        InvalidNumbers::new(nums.inspect(|&num| seq.push(num)), options.preamble)
+            .map(|(position, num)| {
+                println!("{} at position {} is invalid", num, position);
+                num
+            })
+            .collect()
+    })?;
+
+    let num = match invalid.first() {
+        Some(&num) => num,
+        None => {
+            println!("all the numbers are okay");
+            return Ok(());
+        }
+    };
     let weakness: i64 = find_contiguous_sum(&seq, num);
     println!("number out of window is {}", num);
     println!("encryption weakness is {}", weakness);
+
+    // Count the ranges of at least two numbers for every invalid number in one pass
+    let mut range_counts: HashMap<i64, usize> = HashMap::new();
+    for (target, _range) in ContiguousRanges::with_targets(seq.iter().copied(), &invalid)
+        .filter(|(_target, range)| range.len() >= 2)
+    {
+        *range_counts.entry(target).or_insert(0) += 1;
+    }
+    for target in invalid {
+        println!(
+            "{} ranges add up to {}",
+            range_counts.get(&target).unwrap_or(&0),
+            target
+        );
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    /// The first number that isn't the sum of two of the `window_size` numbers before it.
+    fn number_out_of_window(seq: &[i64], window_size: usize) -> Option<i64> {
+        InvalidNumbers::new(seq.iter().copied(), window_size)
+            .next()
+            .map(|(_position, num)| num)
+    }
+
     #[test]
     fn test_out_of_window() {
         let seq = vec![
             35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277, 309,
             576,
         ];
-        assert_eq!(number_out_of_window(&seq, 5), 127);
+        assert_eq!(number_out_of_window(&seq, 5), Some(127));
 
         let seq = vec![
             35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127,
         ];
-        assert_eq!(number_out_of_window(&seq, 5), 127);
-        assert_eq!(number_out_of_window(&seq, 2), 15);
+        assert_eq!(number_out_of_window(&seq, 5), Some(127));
+        assert_eq!(number_out_of_window(&seq, 2), Some(15));
+        assert_eq!(number_out_of_window(&seq[..5], 5), None);
     }
 
     #[test]
@@ -86,4 +179,17 @@ mod test {
         ];
         assert_eq!(find_contiguous_sum(&seq, 127), 62);
     }
+
+    #[test]
+    fn test_contiguous_range() {
+        let seq = vec![3, 0, 4, 1, 5, 9, 2, 6];
+        assert_eq!(find_contiguous_range(&seq, 15), Some(3..6));
+        assert_eq!(find_contiguous_range(&seq, 0), Some(1..2));
+        assert_eq!(find_contiguous_range(&seq, 100), None);
+
+        let seq = vec![5, -3, 8, -10, 2, 7];
+        assert_eq!(find_contiguous_range(&seq, 10), Some(0..3));
+        assert_eq!(find_contiguous_range(&seq, -1), Some(3..6));
+        assert_eq!(find_contiguous_range(&seq, 20), None);
+    }
 }
@@ -0,0 +1,278 @@
+use eyre::{eyre, Report, Result};
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::ops::Range;
+
+/// A sliding window over the most recent numbers of an XMAS stream. Along with the
+/// numbers in order, it keeps a count of how many times each value appears, so that
+/// checking whether a pair sums to a value takes O(w) time instead of O(w²).
+pub struct Window {
+    size: usize,
+    order: VecDeque<i64>,
+    counts: HashMap<i64, usize>,
+}
+
+impl Window {
+    pub fn new(size: usize) -> Self {
+        Window {
+            size,
+            order: VecDeque::with_capacity(size + 1),
+            counts: HashMap::with_capacity(size),
+        }
+    }
+
+    /// Whether the window has seen enough numbers to start validating.
+    pub fn is_full(&self) -> bool {
+        self.order.len() == self.size
+    }
+
+    /// Add a number to the window, pushing out the oldest number if the window is full.
+    pub fn push(&mut self, num: i64) {
+        self.order.push_back(num);
+        *self.counts.entry(num).or_insert(0) += 1;
+        if self.order.len() > self.size {
+            let oldest = self.order.pop_front().unwrap();
+            let count = self.counts.get_mut(&oldest).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(&oldest);
+            }
+        }
+    }
+
+    /// Whether two different values in the window add up to `sum`.
+    pub fn has_pair_summing_to(&self, sum: i64) -> bool {
+        self.counts
+            .keys()
+            .any(|&num| num != sum - num && self.counts.contains_key(&(sum - num)))
+    }
+}
+
+/// Read one number per line from the lines of any reader, such as a file or stdin.
+/// Blank lines are skipped, so a feed can be padded with them.
+pub fn read_numbers<L>(lines: L) -> impl Iterator<Item = Result<i64>>
+where
+    L: Iterator<Item = io::Result<String>>,
+{
+    lines.enumerate().filter_map(|(line_num, line)| match line {
+        Err(err) => Some(Err(err.into())),
+        Ok(line) if line.trim().is_empty() => None,
+        Ok(line) => Some(
+            line.trim()
+                .parse()
+                .map_err(|_| eyre!("line {}: {:?} is not a number", line_num + 1, line.trim())),
+        ),
+    })
+}
+
+/// Stops at the first error in a stream of results, and keeps the error for later.
+pub struct UntilError<'a, I> {
+    results: I,
+    error: &'a mut Option<Report>,
+}
+
+impl<'a, I: Iterator<Item = Result<i64>>> Iterator for UntilError<'a, I> {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<i64> {
+        match self.results.next()? {
+            Ok(num) => Some(num),
+            Err(err) => {
+                *self.error = Some(err);
+                None
+            }
+        }
+    }
+}
+
+/// Run `f` on the numbers from a stream that can fail, such as `read_numbers`, so that
+/// it can be fed to `InvalidNumbers` or `ContiguousRanges` without collecting it
+/// first. If the stream hits an error, `f` sees the numbers up to that point, and
+/// then the error is returned instead of its result.
+pub fn process_numbers<I, T, F>(results: I, f: F) -> Result<T>
+where
+    I: Iterator<Item = Result<i64>>,
+    F: FnOnce(UntilError<'_, I>) -> T,
+{
+    let mut error = None;
+    let value = f(UntilError {
+        results,
+        error: &mut error,
+    });
+    match error {
+        Some(err) => Err(err),
+        None => Ok(value),
+    }
+}
+
+/// An iterator adapter that yields every number in an XMAS stream that isn't the sum
+/// of two different numbers in the window before it, along with its position in the
+/// stream. The first `preamble` numbers are never reported.
+pub struct InvalidNumbers<I> {
+    numbers: I,
+    window: Window,
+    position: usize,
+}
+
+impl<I: Iterator<Item = i64>> InvalidNumbers<I> {
+    pub fn new(numbers: I, preamble: usize) -> Self {
+        InvalidNumbers {
+            numbers,
+            window: Window::new(preamble),
+            position: 0,
+        }
+    }
+}
+
+impl<I: Iterator<Item = i64>> Iterator for InvalidNumbers<I> {
+    type Item = (usize, i64);
+
+    fn next(&mut self) -> Option<(usize, i64)> {
+        for num in &mut self.numbers {
+            let position = self.position;
+            self.position += 1;
+            let valid = !self.window.is_full() || self.window.has_pair_summing_to(num);
+            self.window.push(num);
+            if !valid {
+                return Some((position, num));
+            }
+        }
+        None
+    }
+}
+
+/// An iterator adapter that yields every contiguous range of a stream of numbers that
+/// adds up to one of the `targets`, along with that target, as soon as the last
+/// number in the range arrives. Ranges that end at the same position come out in
+/// order of target, then of where they start.
+///
+/// All the targets are checked in the same pass over the stream. This remembers every
+/// running sum it has seen, so it takes memory proportional to the length of the
+/// stream so far.
+pub struct ContiguousRanges<I> {
+    numbers: I,
+    targets: Vec<i64>,
+    total: i64,
+    position: usize,
+    sums_at: HashMap<i64, Vec<usize>>,
+    pending: VecDeque<(i64, Range<usize>)>,
+}
+
+impl<I: Iterator<Item = i64>> ContiguousRanges<I> {
+    pub fn with_targets(numbers: I, targets: &[i64]) -> Self {
+        let mut unique_targets: Vec<i64> = Vec::with_capacity(targets.len());
+        for &target in targets {
+            if !unique_targets.contains(&target) {
+                unique_targets.push(target);
+            }
+        }
+        let mut sums_at = HashMap::new();
+        sums_at.insert(0, vec![0]);
+        ContiguousRanges {
+            numbers,
+            targets: unique_targets,
+            total: 0,
+            position: 0,
+            sums_at,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<I: Iterator<Item = i64>> Iterator for ContiguousRanges<I> {
+    type Item = (i64, Range<usize>);
+
+    fn next(&mut self) -> Option<(i64, Range<usize>)> {
+        while self.pending.is_empty() {
+            let num = self.numbers.next()?;
+            self.total += num;
+            self.position += 1;
+            let end = self.position;
+            for &target in &self.targets {
+                if let Some(starts) = self.sums_at.get(&(self.total - target)) {
+                    self.pending
+                        .extend(starts.iter().map(|&start| (target, start..end)));
+                }
+            }
+            self.sums_at
+                .entry(self.total)
+                .or_default()
+                .push(self.position);
+        }
+        self.pending.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::BufRead;
+
+    const EXAMPLE: [i64; 20] = [
+        35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277, 309, 576,
+    ];
+
+    #[test]
+    fn test_invalid_numbers() {
+        let invalid: Vec<(usize, i64)> = InvalidNumbers::new(EXAMPLE.iter().copied(), 5).collect();
+        assert_eq!(invalid, vec![(14, 127)]);
+
+        let invalid: Vec<(usize, i64)> = InvalidNumbers::new(EXAMPLE.iter().copied(), 4).collect();
+        assert_eq!(invalid[..4], [(4, 47), (7, 55), (8, 65), (10, 102)]);
+    }
+
+    #[test]
+    fn test_contiguous_ranges() {
+        let ranges: Vec<Range<usize>> =
+            ContiguousRanges::with_targets(EXAMPLE.iter().copied(), &[127])
+                .map(|(_, range)| range)
+                .collect();
+        assert_eq!(ranges, vec![2..6, 14..15]);
+
+        let ranges: Vec<Range<usize>> =
+            ContiguousRanges::with_targets(vec![1, -1, 1, -1].into_iter(), &[0])
+                .map(|(_, range)| range)
+                .collect();
+        assert_eq!(ranges, vec![0..2, 1..3, 0..4, 2..4]);
+
+        let ranges: Vec<(i64, Range<usize>)> =
+            ContiguousRanges::with_targets(EXAMPLE.iter().copied(), &[127, 35, 127, 70]).collect();
+        assert_eq!(
+            ranges,
+            vec![
+                (35, 0..1),
+                (35, 1..3),
+                (70, 0..3),
+                (127, 2..6),
+                (127, 14..15)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_numbers() {
+        let input = "35\n20\n\n  15 \n";
+        let nums: Vec<i64> = read_numbers(input.as_bytes().lines())
+            .map(|n| n.unwrap())
+            .collect();
+        assert_eq!(nums, vec![35, 20, 15]);
+
+        let mut bad = read_numbers("35\nforty\n".as_bytes().lines());
+        assert!(bad.next().unwrap().is_ok());
+        assert!(bad.next().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_process_numbers() {
+        let input = "35\n20\n15\n25\n47\n40\n";
+        let invalid = process_numbers(read_numbers(input.as_bytes().lines()), |nums| {
+            InvalidNumbers::new(nums, 2).collect::<Vec<_>>()
+        });
+        assert_eq!(invalid.unwrap(), vec![(2, 15), (3, 25), (4, 47), (5, 40)]);
+
+        let result = process_numbers(read_numbers("35\n20\nx\n15\n".as_bytes().lines()), |nums| {
+            nums.count()
+        });
+        assert!(result.is_err());
+    }
+}